@@ -0,0 +1,114 @@
+//! Times a full scan over a generated folder tree.
+//!
+//! The tree is created once under the system temp folder and reused on
+//! later runs. The number of files can be changed with `BENCH_FILES`.
+//!
+//! ```sh
+//! BENCH_FILES=1000000 cargo bench --bench walker
+//! ```
+use folder_stats_tui::{app::Config, event::Event, walker::collect_stats};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// Files created in each leaf folder.
+const FILES_PER_FOLDER: usize = 100;
+/// Sub folders created in each folder.
+const FANOUT: usize = 10;
+/// Scans to run.
+const ITERATIONS: usize = 5;
+
+fn main() {
+    let files = std::env::var("BENCH_FILES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1_000_000);
+    let root = std::env::temp_dir().join(format!("folder-stats-bench-{files}"));
+    generate_tree(&root, files);
+
+    let config = Config {
+        root_paths: Box::leak(Box::new([root.canonicalize().expect("bench tree")])),
+        filters: Vec::new(),
+        no_ignores: true,
+        depth: 1,
+        show_hidden: true,
+        disk_usage: false,
+        no_dedup: false,
+        one_file_system: false,
+        follow_links: false,
+        excludes: &[],
+        watch: false,
+        refresh: None,
+    };
+
+    let mut timings = (0..ITERATIONS)
+        .map(|_| scan(config.clone()))
+        .collect::<Vec<_>>();
+    timings.sort();
+    println!(
+        "scan {files} files: min {:?} median {:?} max {:?}",
+        timings[0],
+        timings[ITERATIONS / 2],
+        timings[ITERATIONS - 1]
+    );
+}
+
+/// Run a scan and wait for it to complete.
+fn scan(config: Config) -> Duration {
+    let (sender, receiver) = mpsc::channel();
+    let start = Instant::now();
+    collect_stats(sender, config);
+    while let Ok(event) = receiver.recv() {
+        if let Event::ScanComplete(..) = event {
+            break;
+        }
+    }
+    start.elapsed()
+}
+
+/// Create `files` empty files spread over nested folders.
+fn generate_tree(root: &Path, files: usize) {
+    let marker = root.join(".complete");
+    if marker.exists() {
+        return;
+    }
+    let leaves = files.div_ceil(FILES_PER_FOLDER);
+    // Enough levels for a distinct path for every leaf.
+    let mut levels = 1;
+    while FANOUT.pow(levels) < leaves {
+        levels += 1;
+    }
+    let mut created = 0;
+    for leaf in 0..leaves {
+        let folder = leaf_path(root, leaf, levels as usize);
+        fs::create_dir_all(&folder).expect("create bench folder");
+        for file in 0..FILES_PER_FOLDER.min(files - created) {
+            fs::write(folder.join(format!("file-{file}.txt")), b"folder stats")
+                .expect("create bench file");
+        }
+        created += FILES_PER_FOLDER;
+    }
+    fs::write(marker, b"").expect("create bench marker");
+}
+
+/// Spread leaf folders `FANOUT` wide at each level.
+fn leaf_path(root: &Path, mut leaf: usize, levels: usize) -> PathBuf {
+    let mut path = root.to_path_buf();
+    let mut parts = Vec::new();
+    loop {
+        parts.push(leaf % FANOUT);
+        leaf /= FANOUT;
+        if leaf == 0 {
+            break;
+        }
+    }
+    // Keep every leaf at the same depth.
+    parts.resize(levels, 0);
+    for part in parts.iter().rev() {
+        path.push(format!("d{part}"));
+    }
+    path
+}
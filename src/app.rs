@@ -1,6 +1,27 @@
 //! Application configuration and state.
-use crate::args::Args;
-use std::{borrow::Cow, cmp::Reverse, collections::HashMap, path::PathBuf, time::Duration};
+use crate::{
+    args::Args,
+    diff::Baseline,
+    largest::{LargeFile, LargestFiles},
+    ncdu,
+    report::OutputFormat,
+    snapshot,
+    tree::{FolderKind, FolderTree},
+    walker::{self, ScanHandle, ScanProgress, WalkError},
+    watch::{ChangedFolder, FolderUpdate, FolderWatcher},
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    ops::{AddAssign, SubAssign},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 /// Sorting options for folders
 #[derive(Debug, Copy, Clone, Default)]
@@ -13,68 +34,395 @@ pub enum SortBy {
 }
 
 /// Filters to apply to scan.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Filter {
-    /// File name extension filter.
+    /// Exact file name extension.
     Extension(String),
-    /// File name filter.
-    FileName(String),
+    /// Shell glob matched against the file name.
+    Glob(String),
+    /// Regular expression matched against the file name.
+    Regex(String),
+    /// Text contained in the file name.
+    #[serde(alias = "FileName")]
+    Contains(String),
+    /// Minimum file size in bytes.
+    MinSize(u64),
+    /// Maximum file size in bytes.
+    MaxSize(u64),
+    /// Files last modified before this time.
+    OlderThan(DateTime<Local>),
+    /// Files last modified after this time.
+    NewerThan(DateTime<Local>),
 }
 
 impl Filter {
-    pub fn contains(&self, test: &str) -> bool {
+    /// True for filters matching the file name.
+    pub fn is_file_name(&self) -> bool {
+        matches!(
+            self,
+            Filter::Glob(_) | Filter::Regex(_) | Filter::Contains(_)
+        )
+    }
+
+    /// True for filters on the file size or modification time.
+    pub fn is_size_or_time(&self) -> bool {
+        matches!(
+            self,
+            Filter::MinSize(_) | Filter::MaxSize(_) | Filter::OlderThan(_) | Filter::NewerThan(_)
+        )
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Filter::Extension(s) => test.contains(s),
-            Filter::FileName(s) => test.contains(s),
+            Filter::Extension(s) | Filter::Glob(s) => f.write_str(s),
+            Filter::Regex(s) => write!(f, "regex:{s}"),
+            Filter::Contains(s) => write!(f, "contains:{s}"),
+            Filter::MinSize(size) => write!(f, "min:{}", bytesize::ByteSize(*size)),
+            Filter::MaxSize(size) => write!(f, "max:{}", bytesize::ByteSize(*size)),
+            Filter::OlderThan(time) => write!(f, "older:{}", time.format("%Y-%m-%d")),
+            Filter::NewerThan(time) => write!(f, "newer:{}", time.format("%Y-%m-%d")),
         }
     }
 }
 
 /// Statistics for a folder.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct FolderStat {
     /// Recursive total file sizes.
     pub size: u64,
+    /// Recursive total disk usage from allocated blocks.
+    pub disk_size: u64,
     /// Recursive total file count.
     pub files: usize,
 }
 
+impl FolderStat {
+    /// Size used for bars and sorting.
+    pub fn measured_size(&self, disk_usage: bool) -> u64 {
+        if disk_usage {
+            self.disk_size
+        } else {
+            self.size
+        }
+    }
+}
+
+impl AddAssign for FolderStat {
+    fn add_assign(&mut self, rhs: Self) {
+        self.size += rhs.size;
+        self.disk_size += rhs.disk_size;
+        self.files += rhs.files;
+    }
+}
+
+impl SubAssign for FolderStat {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.size -= rhs.size;
+        self.disk_size -= rhs.disk_size;
+        self.files -= rhs.files;
+    }
+}
+
 /// Application configuration sourced
 /// from command line argument options.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Config {
-    /// Path to scan.
-    pub root_path: &'static PathBuf,
+    /// Paths to scan.
+    pub root_paths: &'static [PathBuf],
     /// Filters for scan.
-    pub filters: &'static [Filter],
+    pub filters: Vec<Filter>,
+    /// Glob patterns of files and folders to skip.
+    pub excludes: &'static [String],
     /// Disable ignores support.
     pub no_ignores: bool,
-    /// Initial depth to render on first scan.
+    /// Initial depth to render.
     pub depth: u8,
     /// Disable showing hidden files.
     pub show_hidden: bool,
+    /// Use disk usage instead of apparent size for bars and sorting.
+    pub disk_usage: bool,
+    /// Count hard linked files each time they are found.
+    pub no_dedup: bool,
+    /// Do not cross file system boundaries.
+    pub one_file_system: bool,
+    /// Follow symbolic links.
+    pub follow_links: bool,
+    /// Update folders that change after a scan completes.
+    pub watch: bool,
+    /// Rescan after this long since the last scan completed.
+    pub refresh: Option<Duration>,
+}
+
+/// Names of the folders grouping the scanned paths. A single scanned
+/// path is the root folder and is not grouped.
+pub fn root_groups(root_paths: &[PathBuf]) -> Vec<String> {
+    match root_paths {
+        [_] => Vec::new(),
+        roots => roots
+            .iter()
+            .map(|root| root.to_string_lossy().into_owned())
+            .collect(),
+    }
+}
+
+impl Config {
+    /// Prefix removed from walked paths to get folder names. Folders are
+    /// named by absolute path when scanning several paths.
+    pub fn name_prefix(&self) -> &'static [u8] {
+        match self.root_paths {
+            [root] => root.as_os_str().as_bytes(),
+            _ => b"",
+        }
+    }
+
+    /// Names of the scanned paths grouped below an unnamed root folder
+    /// when scanning several paths.
+    pub fn groups(&self) -> Vec<String> {
+        root_groups(self.root_paths)
+    }
+
+    /// Path of a folder from its name. There is no path for the root
+    /// folder grouping several scanned paths.
+    pub fn folder_path(&self, name: &str) -> Option<PathBuf> {
+        match self.root_paths {
+            [root] => Some(root.join(name.trim_start_matches('/'))),
+            _ if name.is_empty() => None,
+            _ => Some(PathBuf::from(name)),
+        }
+    }
 }
 
 impl TryFrom<Args> for Config {
     type Error = anyhow::Error;
 
     fn try_from(args: Args) -> Result<Self, Self::Error> {
-        Ok(Self {
-            root_path: Box::leak(Box::new(args.root_path.canonicalize()?)),
+        let mut root_paths = Vec::new();
+        for path in &args.root_paths {
+            let path = path
+                .canonicalize()
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if !root_paths.contains(&path) {
+                root_paths.push(path);
+            }
+        }
+        for path in &root_paths {
+            if let Some(outer) = root_paths
+                .iter()
+                .find(|outer| *outer != path && path.starts_with(outer))
+            {
+                bail!(
+                    "{} is inside {}, paths to scan cannot overlap",
+                    path.display(),
+                    outer.display()
+                );
+            }
+        }
+
+        let mut excludes = args.exclude.clone();
+        if let Some(path) = &args.exclude_from {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            excludes.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(ToOwned::to_owned),
+            );
+        }
+
+        let config = Self {
+            root_paths: Box::leak(root_paths.into_boxed_slice()),
+            excludes: Box::leak(excludes.into_boxed_slice()),
             no_ignores: args.no_ignores,
             show_hidden: args.show_hidden,
             depth: args.depth,
-            filters: Box::leak(Box::new(args.filters())),
+            disk_usage: args.disk_usage,
+            no_dedup: args.no_dedup,
+            one_file_system: args.one_file_system,
+            follow_links: args.follow_links,
+            watch: args.watch,
+            refresh: args.refresh.map(Duration::from_secs),
+            filters: args.filters(),
+        };
+        walker::exclude_overrides(&config).context("Invalid exclude pattern")?;
+        Ok(config)
+    }
+}
+
+/// Prompt for exporting the displayed folders to a file.
+#[derive(Debug, Clone)]
+pub struct ExportPrompt {
+    /// File name to write.
+    pub file_name: String,
+    /// Format to write.
+    pub format: OutputFormat,
+}
+
+impl Default for ExportPrompt {
+    fn default() -> Self {
+        let format = OutputFormat::Json;
+        Self {
+            file_name: format!("folder-stats.{}", format.extension()),
+            format,
+        }
+    }
+}
+
+impl ExportPrompt {
+    /// Switch to the next format, updating the file name extension.
+    pub fn next_format(&mut self) {
+        let next = self.format.next();
+        if let Some(stem) = self
+            .file_name
+            .strip_suffix(self.format.extension())
+            .and_then(|s| s.strip_suffix('.'))
+        {
+            self.file_name = format!("{stem}.{}", next.extension());
+        }
+        self.format = next;
+    }
+}
+
+/// Kind of filter edited in the [`FilterEditor`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum FilterKind {
+    #[default]
+    /// File name glob.
+    Glob,
+    /// File name regular expression.
+    Regex,
+    /// Text contained in the file name.
+    Contains,
+    /// Exact file name extension.
+    Extension,
+}
+
+impl FilterKind {
+    /// Next kind when cycling through kinds.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Glob => Self::Regex,
+            Self::Regex => Self::Contains,
+            Self::Contains => Self::Extension,
+            Self::Extension => Self::Glob,
+        }
+    }
+
+    /// Parse `text` into a filter of this kind.
+    pub fn parse(self, text: &str) -> Result<Filter, String> {
+        match self {
+            Self::Glob => globset::Glob::new(text)
+                .map(|_| Filter::Glob(text.to_owned()))
+                .map_err(|err| err.to_string()),
+            Self::Regex => regex::Regex::new(text)
+                .map(|_| Filter::Regex(text.to_owned()))
+                .map_err(|err| err.to_string()),
+            Self::Contains => Ok(Filter::Contains(text.to_owned())),
+            Self::Extension => Ok(Filter::Extension(text.trim_start_matches('.').to_owned())),
+        }
+    }
+
+    /// Kind and text of a file name or extension filter.
+    fn split(filter: &Filter) -> Option<(Self, &str)> {
+        match filter {
+            Filter::Glob(s) => Some((Self::Glob, s)),
+            Filter::Regex(s) => Some((Self::Regex, s)),
+            Filter::Contains(s) => Some((Self::Contains, s)),
+            Filter::Extension(s) => Some((Self::Extension, s)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Glob => "glob",
+            Self::Regex => "regex",
+            Self::Contains => "contains",
+            Self::Extension => "extension",
         })
     }
 }
 
+/// Editor for the file name and extension filters, applied with a rescan.
+#[derive(Debug, Clone, Default)]
+pub struct FilterEditor {
+    /// File name and extension filters to scan with.
+    pub filters: Vec<Filter>,
+    /// Index of the selected filter, or the count of filters when adding one.
+    pub selected: usize,
+    /// Text of the filter being added or changed.
+    pub input: String,
+    /// Kind of filter the input is parsed as.
+    pub kind: FilterKind,
+    /// Error from parsing the input.
+    pub error: Option<String>,
+}
+
+impl FilterEditor {
+    /// Editor for the file name and extension filters of `filters`.
+    pub fn new(filters: &[Filter]) -> Self {
+        let filters = filters
+            .iter()
+            .filter(|f| FilterKind::split(f).is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        Self {
+            selected: filters.len(),
+            filters,
+            ..Default::default()
+        }
+    }
+
+    /// Select the filter at `index`, or the new filter input past the
+    /// last filter, loading the selected filter into the input.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.filters.len());
+        self.error = None;
+        match self.filters.get(self.selected).and_then(FilterKind::split) {
+            Some((kind, text)) => {
+                self.kind = kind;
+                self.input = text.to_owned();
+            }
+            None => self.input.clear(),
+        }
+    }
+
+    /// Add the input as a new filter or replace the selected filter.
+    pub fn submit(&mut self) {
+        match self.kind.parse(&self.input) {
+            Ok(filter) => {
+                match self.filters.get_mut(self.selected) {
+                    Some(selected) => *selected = filter,
+                    None => self.filters.push(filter),
+                }
+                self.select(self.filters.len());
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    /// Remove the selected filter.
+    pub fn remove(&mut self) {
+        if self.selected < self.filters.len() {
+            self.filters.remove(self.selected);
+            self.select(self.selected);
+        }
+    }
+}
+
 /// Application State.
 #[derive(Debug)]
 pub struct App {
-    /// Name of the folder being scanned.
-    pub folder_name: String,
-    /// Result of scanned folders with folder stats.
+    /// Progress of the scan in progress or final counters of the last scan.
+    pub progress: ScanProgress,
+    /// Scanned folder hierarchy.
+    pub tree: FolderTree,
+    /// Folders displayed from the scanned hierarchy with folder stats.
     pub scan_result: Vec<(String, FolderStat)>,
     /// Flag to terminate program.
     pub should_quit: bool,
@@ -84,6 +432,8 @@ pub struct App {
     pub max_scroll: usize,
     /// True if we are scanning folders.
     pub scanning: bool,
+    /// Scan currently in progress.
+    pub scan: Option<ScanHandle>,
     /// Depth to report on.
     pub depth: u8,
     /// Sorting of folders.
@@ -92,12 +442,57 @@ pub struct App {
     pub content_height: u16,
     /// Folder events emitted by walker.
     pub folder_events: HashMap<String, FolderStat>,
+    /// True if folder events were received since the display was updated.
+    pub partial_result: bool,
+    /// Folders marked by the walker such as skipped mount points.
+    pub marked_folders: Vec<(String, FolderKind)>,
     /// Initial configuration from program launch.
     pub config: Config,
     /// Show help popup.
     pub show_help: bool,
+    /// Errors encountered while walking.
+    pub walk_errors: Vec<WalkError>,
+    /// Show walk errors popup.
+    pub show_errors: bool,
+    /// Index for where walk errors scrolling is set.
+    pub error_scroll: usize,
+    /// Export prompt when open.
+    pub export: Option<ExportPrompt>,
+    /// Filter editor when open.
+    pub filter_editor: Option<FilterEditor>,
+    /// Outcome of the last action such as an export, and if it succeeded.
+    pub status: Option<(bool, String)>,
+    /// File the scan was loaded from, an ncdu export or a snapshot,
+    /// instead of walking folders.
+    pub loaded: Option<PathBuf>,
+    /// Snapshot file written when a scan completes.
+    pub save_path: Option<PathBuf>,
+    /// When the displayed scan completed.
+    pub scanned_at: Option<DateTime<Local>>,
+    /// Total of the scanned folder from the scan before the last rescan.
+    pub previous_total: Option<FolderStat>,
+    /// Watcher for changes after the scan completed.
+    pub watcher: Option<FolderWatcher>,
+    /// Folders with entries changed since the display was updated.
+    pub changed_folders: HashSet<String>,
+    /// True while changed folders are being rescanned.
+    pub rescanning_folders: bool,
+    /// Baseline scan to compare against.
+    pub baseline: Option<Baseline>,
+    /// Show changes from the baseline scan instead of totals.
+    pub show_diff: bool,
+    /// Largest files of the scan.
+    pub largest_files: LargestFiles,
+    /// Largest files of a drilled in folder, once walked.
+    pub folder_largest_files: Option<(String, Option<Vec<LargeFile>>)>,
+    /// Show the largest files instead of folders.
+    pub show_largest: bool,
     /// Time take to scan
     pub scan_time: Duration,
+    /// Index of the selected folder in the scan result.
+    pub selected: usize,
+    /// Folders drilled into from the root folder.
+    pub view_path: Vec<String>,
 }
 
 impl App {
@@ -108,9 +503,11 @@ impl App {
     pub fn new(config: Config) -> Self {
         Self {
             scanning: true,
+            scan: None,
             depth: config.depth,
             config,
-            folder_name: String::new(),
+            progress: ScanProgress::default(),
+            tree: FolderTree::default(),
             scan_result: Vec::new(),
             should_quit: false,
             scroll_state: 0,
@@ -118,26 +515,337 @@ impl App {
             sort: Default::default(),
             content_height: 0,
             folder_events: HashMap::new(),
+            partial_result: false,
+            marked_folders: Vec::new(),
             show_help: false,
+            walk_errors: Vec::new(),
+            show_errors: false,
+            error_scroll: 0,
+            export: None,
+            filter_editor: None,
+            status: None,
+            loaded: None,
+            save_path: None,
+            scanned_at: None,
+            previous_total: None,
+            watcher: None,
+            changed_folders: HashSet::new(),
+            rescanning_folders: false,
+            baseline: None,
+            show_diff: false,
+            largest_files: LargestFiles::default(),
+            folder_largest_files: None,
+            show_largest: false,
             scan_time: Default::default(),
+            selected: 1,
+            view_path: Vec::new(),
         }
     }
 
-    /// Update scan progress with folder being scanned.
-    pub fn update_progress(&mut self, folder_name: String) {
-        self.folder_name = folder_name;
+    /// Update scan progress with the latest walker counters.
+    pub fn update_progress(&mut self, progress: ScanProgress) {
+        self.progress = progress;
+    }
+
+    /// Load a scan from an ncdu export instead of walking the file system.
+    pub fn import_ncdu(&mut self, path: &Path) -> Result<()> {
+        let scan = ncdu::import(path)?;
+        self.config.root_paths = Box::leak(Box::new([scan.root]));
+        self.marked_folders = scan.marked;
+        self.walk_errors = scan.errors;
+        self.update_scan_result(scan.folders);
+        self.scanning = false;
+        self.scanned_at = scan.timestamp;
+        self.loaded = Some(path.to_owned());
+        Ok(())
+    }
+
+    /// Load a saved scan instead of walking the file system.
+    pub fn load_snapshot(&mut self, path: &Path) -> Result<()> {
+        let snapshot = snapshot::load(path)?;
+        self.config = snapshot.config(self.config.clone());
+        self.marked_folders = snapshot.marked;
+        self.walk_errors = snapshot.errors;
+        self.update_scan_result(snapshot.folders.into_iter().collect());
+        self.scanning = false;
+        self.scan_time = snapshot.scan_time;
+        self.scanned_at = Some(snapshot.timestamp);
+        self.loaded = Some(path.to_owned());
+        Ok(())
     }
 
     /// Update state with scan results.
     pub fn update_scan_result(&mut self, result: HashMap<String, FolderStat>) {
-        let mut file_rows = result.into_iter().collect::<Vec<_>>();
-        file_rows.sort_unstable_by_key(|(_, v)| Reverse(v.size));
-        self.sort = SortBy::FileSize;
-        self.scan_result = file_rows;
+        let selected = self.selected_folder().map(ToOwned::to_owned);
+        self.set_tree(self.build_tree(result));
+        // Folders drilled into may no longer exist after a rescan.
+        while self.tree.find(self.view_root()).is_none() && self.drill_up().is_some() {}
+        self.refresh_view();
+        if let Some(folder) = selected {
+            self.select_folder(&folder);
+        }
+        self.partial_result = false;
+    }
+
+    /// Add folder stats emitted by a running scan to the scanned
+    /// hierarchy. The first stats of a scan replace the previous scan.
+    pub fn add_folder_events(&mut self, events: HashMap<String, FolderStat>) {
+        if self.folder_events.is_empty() {
+            self.set_tree(self.build_tree(std::iter::empty::<(&str, FolderStat)>()));
+        }
+        for (name, stats) in events {
+            self.tree.add_folder_files(&name, stats);
+            *self.folder_events.entry(name).or_default() += stats;
+        }
+        self.partial_result = true;
+    }
+
+    /// Mark a folder reported by a running scan.
+    pub fn mark_folder(&mut self, name: String, kind: FolderKind) {
+        self.tree.mark(&name, kind);
+        self.marked_folders.push((name, kind));
+        self.partial_result = true;
+    }
+
+    /// Update the displayed folders with the results emitted so far by
+    /// a running scan, keeping the selected folder.
+    pub fn update_partial_result(&mut self) {
+        let selected = self.selected_folder().map(ToOwned::to_owned);
+        self.refresh_view();
+        if let Some(folder) = selected {
+            self.select_folder(&folder);
+        }
+        self.partial_result = false;
+    }
+
+    /// Take the folders with changed entries to rescan. Changes in
+    /// folders that were not scanned, such as ignored folders, are skipped.
+    pub fn take_changed_folders(&mut self) -> Vec<ChangedFolder> {
+        std::mem::take(&mut self.changed_folders)
+            .into_iter()
+            .filter(|name| self.config.folder_path(name).is_some())
+            .filter_map(|name| {
+                let index = self.tree.find(&name).filter(|&index| {
+                    matches!(
+                        self.tree.node(index).kind,
+                        FolderKind::Folder | FolderKind::Symlink
+                    )
+                })?;
+                let known_folders = self
+                    .tree
+                    .node(index)
+                    .children
+                    .iter()
+                    .map(|&child| self.tree.node(child))
+                    .filter(|child| child.kind != FolderKind::Deleted)
+                    .map(|child| child.name.clone())
+                    .collect();
+                Some(ChangedFolder {
+                    name,
+                    known_folders,
+                })
+            })
+            .collect()
+    }
+
+    /// Apply rescanned folders, updating their ancestors, and keep the
+    /// selected folder.
+    pub fn update_changed_folders(&mut self, updates: Vec<FolderUpdate>) {
+        let selected = self.selected_folder().map(ToOwned::to_owned);
+        for update in updates {
+            self.update_folder(update);
+        }
+        self.refresh_view();
+        if let Some(folder) = selected {
+            self.select_folder(&folder);
+        }
+    }
+
+    /// Replace the stats of the files directly inside a folder, adding new
+    /// sub folders and removing missing ones.
+    fn update_folder(&mut self, update: FolderUpdate) {
+        // Folders removed by an earlier update are skipped.
+        let Some(index) = self.tree.find(&update.name).filter(|&index| {
+            matches!(
+                self.tree.node(index).kind,
+                FolderKind::Folder | FolderKind::Symlink
+            )
+        }) else {
+            return;
+        };
+        let removed = self
+            .tree
+            .node(index)
+            .children
+            .iter()
+            .map(|&child| self.tree.node(child))
+            .filter(|child| {
+                child.kind == FolderKind::Folder && !update.folders.contains(&child.name)
+            })
+            .map(|child| child.name.clone())
+            .collect::<Vec<_>>();
+        for child in removed {
+            self.tree.remove(&child);
+        }
+
+        self.tree.set_folder_files(&update.name, update.files);
+        for (folder, files) in update.new_folders {
+            self.tree.set_folder_files(&folder, files);
+        }
+    }
+
+    /// Replace the scanned hierarchy, marking folders reported by the walker
+    /// and folders deleted since the baseline scan.
+    fn set_tree(&mut self, mut tree: FolderTree) {
+        for (name, kind) in &self.marked_folders {
+            tree.mark(name, *kind);
+        }
+        if let Some(baseline) = &self.baseline {
+            baseline.mark_deleted(&mut tree);
+        }
+        self.tree = tree;
+    }
+
+    /// Rebuild the displayed folders from the scanned hierarchy using
+    /// the current view root, depth and sorting.
+    pub fn refresh_view(&mut self) {
+        self.scan_result = self
+            .tree
+            .find(self.view_root())
+            .map(|root| self.tree.view(root, self.depth))
+            .unwrap_or_default();
+        if !self.show_diff {
+            let tree = &self.tree;
+            self.scan_result.retain(|(name, _)| {
+                tree.find(name)
+                    .is_none_or(|index| tree.node(index).kind != FolderKind::Deleted)
+            });
+        }
+        self.sort_scan_result();
         self.scroll_state = 0;
+        self.selected = 1;
         self.compute_max_scroll()
     }
 
+    /// Sort the displayed folders below the displayed root with the current
+    /// sorting. The diff view sorts by the largest change either way.
+    pub fn sort_scan_result(&mut self) {
+        let baseline = self.baseline.as_ref().filter(|_| self.show_diff);
+        if let Some((_, folders)) = self.scan_result.split_first_mut() {
+            sort_folders(folders, self.sort, self.config.disk_usage, baseline);
+        }
+    }
+
+    /// Displayed folders for reports. Reports hold no changes, so folders
+    /// deleted since the baseline are dropped and the diff view is sorted
+    /// with the current sorting.
+    pub fn report_folders(&self) -> Cow<'_, [(String, FolderStat)]> {
+        if !self.show_diff {
+            return Cow::Borrowed(&self.scan_result);
+        }
+        let mut folders = self.scan_result.clone();
+        folders.retain(|(name, _)| self.folder_kind(name) != Some(FolderKind::Deleted));
+        if let Some((_, folders)) = folders.split_first_mut() {
+            sort_folders(folders, self.sort, self.config.disk_usage, None);
+        }
+        Cow::Owned(folders)
+    }
+
+    /// Kind of a folder in the scanned hierarchy.
+    pub fn folder_kind(&self, name: &str) -> Option<FolderKind> {
+        self.tree.find(name).map(|index| self.tree.node(index).kind)
+    }
+
+    /// Select the folder with the given name if it is displayed.
+    pub fn select_folder(&mut self, name: &str) {
+        if let Some(index) = self.scan_result.iter().position(|(n, _)| n == name) {
+            self.selected = index.max(1);
+            self.scroll_to_selected();
+        }
+    }
+
+    /// Relative name of the folder currently used as the displayed root.
+    pub fn view_root(&self) -> &str {
+        self.view_path
+            .last()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Name of the selected folder.
+    pub fn selected_folder(&self) -> Option<&str> {
+        self.scan_result
+            .get(self.selected)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Make the selected folder the displayed root.
+    pub fn drill_down(&mut self) -> bool {
+        match self.selected_folder().map(ToOwned::to_owned) {
+            Some(folder) => {
+                self.view_path.push(folder);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Go back to the parent of the displayed root. Returns the
+    /// folder that was displayed.
+    pub fn drill_up(&mut self) -> Option<String> {
+        self.view_path.pop()
+    }
+
+    /// Move the selection up.
+    pub fn select_up(&mut self, val: usize) {
+        self.selected = self.selected.saturating_sub(val).max(1);
+        self.scroll_to_selected();
+    }
+
+    /// Move the selection down.
+    pub fn select_down(&mut self, val: usize) {
+        let last = self.scan_result.len().saturating_sub(1).max(1);
+        self.selected = (self.selected + val).min(last);
+        self.scroll_to_selected();
+    }
+
+    /// Adjust the scroll state so the selected folder is visible.
+    fn scroll_to_selected(&mut self) {
+        let page = self.compute_scroll_page().max(1);
+        if self.selected <= self.scroll_state {
+            self.scroll_state = self.selected - 1;
+        } else if self.selected > self.scroll_state + page {
+            self.scroll_state = (self.selected - page).min(self.max_scroll);
+        }
+    }
+
+    /// True if the scan completed longer ago than the refresh interval.
+    pub fn refresh_due(&self) -> bool {
+        match (self.config.refresh, self.scanned_at) {
+            (Some(interval), Some(scanned_at)) if !self.scanning => (Local::now() - scanned_at)
+                .to_std()
+                .is_ok_and(|elapsed| elapsed >= interval),
+            _ => false,
+        }
+    }
+
+    /// Largest files inside the displayed root folder, or `None` when
+    /// the folder still needs to be walked.
+    pub fn view_largest_files(&self) -> Option<Vec<LargeFile>> {
+        let root = self.view_root();
+        match &self.folder_largest_files {
+            Some((name, files)) if name == root => files.clone(),
+            _ => self.largest_files.in_folder(root),
+        }
+    }
+
+    /// True if events from the given scan generation belong to the current scan.
+    pub fn is_current_scan(&self, generation: u64) -> bool {
+        self.scan
+            .as_ref()
+            .is_some_and(|scan| scan.generation == generation)
+    }
+
     /// Signal program termination.
     pub fn quit(&mut self) {
         self.should_quit = true;
@@ -145,8 +853,9 @@ impl App {
 
     /// Scroll up.
     pub fn scroll_up(&mut self, val: usize) {
-        let up = self.scroll_state.checked_sub(val).unwrap_or_default();
+        let up = self.scroll_state.saturating_sub(val);
         self.scroll_state = up;
+        self.select_in_view();
     }
 
     /// Scroll down.
@@ -157,6 +866,16 @@ impl App {
         } else {
             self.scroll_state = self.max_scroll;
         }
+        self.select_in_view();
+    }
+
+    /// Move the selection onto the visible page after scrolling the view.
+    fn select_in_view(&mut self) {
+        let page = self.compute_scroll_page().max(1);
+        let last = self.scan_result.len().saturating_sub(1).max(1);
+        let first_visible = self.scroll_state + 1;
+        let last_visible = (self.scroll_state + page).min(last).max(first_visible);
+        self.selected = self.selected.clamp(first_visible, last_visible);
     }
 
     /// Compute what the maximum scroll index should be based
@@ -183,12 +902,134 @@ impl App {
         (self.content_height / Self::FOLDER_ITEM_HEIGHT) as usize
     }
 
+    /// Depth of a folder below the scanned folder.
+    pub fn folder_depth(&self, name: &str) -> usize {
+        self.tree
+            .find(name)
+            .map(|index| self.tree.node(index).depth)
+            .unwrap_or_default()
+    }
+
+    /// Canonical names of the scanned folders.
     pub fn root_folder(&self) -> Cow<'_, str> {
-        self.config
-            .root_path
-            .as_path()
-            .to_str()
-            .map(Into::into)
-            .unwrap_or(self.config.root_path.to_string_lossy())
+        match self.config.root_paths {
+            [root] => root.to_string_lossy(),
+            roots => roots
+                .iter()
+                .map(|root| root.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ")
+                .into(),
+        }
+    }
+
+    /// Path of the folder used as the displayed root.
+    pub fn view_folder(&self) -> Cow<'_, str> {
+        match self.view_root() {
+            "" => self.root_folder(),
+            name => match self.config.folder_path(name) {
+                Some(path) => path.to_string_lossy().into_owned().into(),
+                None => self.root_folder(),
+            },
+        }
+    }
+
+    /// Short name of a folder, the full path for scanned folders grouped
+    /// below the root.
+    pub fn short_name<'a>(&self, name: &'a str) -> &'a str {
+        if self.config.root_paths.len() > 1 && self.folder_depth(name) == 1 {
+            name
+        } else {
+            name.rsplit('/').next().unwrap_or(name)
+        }
+    }
+
+    /// Build the folder hierarchy from the stats of files directly inside
+    /// each folder, grouping the scanned paths when there are several.
+    fn build_tree<S: AsRef<str>>(
+        &self,
+        stats: impl IntoIterator<Item = (S, FolderStat)>,
+    ) -> FolderTree {
+        FolderTree::from_grouped_stats(&self.config.groups(), stats)
+    }
+}
+
+/// Sort folders from largest to smallest, by their change from a baseline
+/// when given.
+fn sort_folders(
+    folders: &mut [(String, FolderStat)],
+    sort: SortBy,
+    disk_usage: bool,
+    baseline: Option<&Baseline>,
+) {
+    folders.sort_by_key(|(name, stats)| {
+        Reverse(match (sort, baseline) {
+            (SortBy::FileSize, None) => stats.measured_size(disk_usage),
+            (SortBy::FileCount, None) => stats.files as u64,
+            (SortBy::FileSize, Some(baseline)) => baseline
+                .delta(name, *stats)
+                .measured_size(disk_usage)
+                .unsigned_abs(),
+            (SortBy::FileCount, Some(baseline)) => {
+                baseline.delta(name, *stats).files.unsigned_abs()
+            }
+        })
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{App, Config, Filter, FilterEditor, FilterKind, FolderStat};
+    use crate::args::Args;
+    use clap::Parser;
+
+    #[test]
+    fn test_scroll_keeps_selection_visible() {
+        let mut app = App::new(Config::try_from(Args::parse_from(["test"])).unwrap());
+        app.update_scan_result(
+            (0..10)
+                .map(|i| (format!("/{i}"), FolderStat::default()))
+                .collect(),
+        );
+        app.content_height = 3 * App::FOLDER_ITEM_HEIGHT;
+        app.compute_max_scroll();
+
+        app.scroll_down(4);
+        assert_eq!((app.scroll_state, app.selected), (4, 5));
+        app.select_down(2);
+        app.scroll_up(3);
+        assert_eq!((app.scroll_state, app.selected), (1, 4));
+    }
+
+    #[test]
+    fn test_filter_editor() {
+        let mut editor = FilterEditor::new(&[
+            Filter::Extension("rs".to_owned()),
+            Filter::MinSize(100),
+            Filter::Glob("*.log".to_owned()),
+        ]);
+        assert_eq!(editor.filters.len(), 2);
+        assert_eq!(editor.selected, 2);
+
+        editor.input.push('[');
+        editor.submit();
+        assert!(editor.error.is_some());
+        editor.kind = FilterKind::Contains;
+        editor.submit();
+        assert!(matches!(&editor.filters[2], Filter::Contains(s) if s == "["));
+
+        editor.select(1);
+        assert_eq!(
+            (editor.kind, editor.input.as_str()),
+            (FilterKind::Glob, "*.log")
+        );
+        editor.input = "*.txt".to_owned();
+        editor.submit();
+        assert!(matches!(&editor.filters[1], Filter::Glob(s) if s == "*.txt"));
+
+        editor.select(0);
+        editor.remove();
+        assert_eq!(editor.filters.len(), 2);
+        assert_eq!(editor.input, "*.txt");
     }
 }
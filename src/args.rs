@@ -1,5 +1,8 @@
-use crate::app::Filter;
+use crate::{app::Filter, report::OutputFormat};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeDelta};
 use clap::Parser;
+use globset::Glob;
+use regex::Regex;
 use std::path::PathBuf;
 
 /// Command line arguments.
@@ -10,24 +13,68 @@ pub struct Args {
         short = 'p',
         long = "path",
         default_value = ".",
-        help = "Folder to scan.",
+        help = "Folder to scan. Repeat to scan several folders.",
         id = "PATH"
     )]
-    pub root_path: PathBuf,
+    pub root_paths: Vec<PathBuf>,
 
     #[arg(short, long, default_value_t = 1, help = "Folder depth to see in Tui")]
     pub depth: u8,
 
     #[arg(short = 'f', long, value_parser = |s: &str| {
-        Ok::<_, std::convert::Infallible>(Filter::FileName(s.to_string()))
-    }, id = "FILENAME_FILTER", help = "Filter files that contain text")]
+        Glob::new(s).map(|_| Filter::Glob(s.to_string()))
+    }, id = "GLOB_FILTER", help = "Filter files whose name matches a glob. Ex: -f '*.log.[0-9]'")]
     pub filter: Vec<Filter>,
 
     #[arg(short = 'e', long = "extension", value_parser = |s: &str| {
-        Ok::<_, std::convert::Infallible>(Filter::Extension(s.to_string()))
-    }, help = "Filter by file extension. Ex: -e rs")]
+        Ok::<_, std::convert::Infallible>(Filter::Extension(s.trim_start_matches('.').to_string()))
+    }, help = "Filter by exact file extension. Ex: -e rs")]
     pub extension_filter: Vec<Filter>,
 
+    #[arg(long, value_parser = |s: &str| {
+        Regex::new(s).map(|_| Filter::Regex(s.to_string()))
+    }, id = "REGEX_FILTER", help = "Filter files whose name matches a regular expression")]
+    pub regex: Vec<Filter>,
+
+    #[arg(long, value_parser = |s: &str| {
+        Ok::<_, std::convert::Infallible>(Filter::Contains(s.to_string()))
+    }, id = "TEXT", help = "Filter files whose name contains text")]
+    pub contains: Vec<Filter>,
+
+    #[arg(long, value_name = "SIZE", value_parser = |s: &str| {
+        parse_size(s).map(Filter::MinSize)
+    }, help = "Only count files at least this large. K, M, G and T are powers of 1024. Ex: --min-size 100M")]
+    pub min_size: Option<Filter>,
+
+    #[arg(long, value_name = "SIZE", value_parser = |s: &str| {
+        parse_size(s).map(Filter::MaxSize)
+    }, help = "Only count files at most this large. K, M, G and T are powers of 1024")]
+    pub max_size: Option<Filter>,
+
+    #[arg(long, value_name = "AGE_OR_DATE", value_parser = |s: &str| {
+        parse_time(s).map(Filter::OlderThan)
+    }, help = "Only count files modified before an age or date. Ex: --older-than 90d")]
+    pub older_than: Option<Filter>,
+
+    #[arg(long, value_name = "AGE_OR_DATE", value_parser = |s: &str| {
+        parse_time(s).map(Filter::NewerThan)
+    }, help = "Only count files modified after an age or date. Ex: --newer-than 2026-01-01")]
+    pub newer_than: Option<Filter>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Skip files and folders matching a glob, even with --no-ignores. Ex: --exclude target/"
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Skip files and folders matching the globs listed in a file, one per line"
+    )]
+    pub exclude_from: Option<PathBuf>,
+
     #[arg(
         short = 'i',
         long,
@@ -38,14 +85,188 @@ pub struct Args {
 
     #[arg(long, default_value_t = false, help = "Disable hidden file filtering")]
     pub show_hidden: bool,
+
+    #[arg(
+        short = 'u',
+        long,
+        default_value_t = false,
+        help = "Size folders by disk usage instead of apparent size"
+    )]
+    pub disk_usage: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Count hard linked files each time they are found"
+    )]
+    pub no_dedup: bool,
+
+    #[arg(
+        short = 'x',
+        long,
+        default_value_t = false,
+        help = "Skip folders on other file systems"
+    )]
+    pub one_file_system: bool,
+
+    #[arg(
+        short = 'L',
+        long,
+        default_value_t = false,
+        help = "Follow symbolic links"
+    )]
+    pub follow_links: bool,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_enum,
+        help = "Write the scan results to stdout in this format instead of starting the Tui"
+    )]
+    pub output: Option<OutputFormat>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Do not start the Tui. Writes JSON unless --output is set"
+    )]
+    pub no_tui: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "PATH",
+        help = "Load an ncdu JSON export instead of scanning"
+    )]
+    pub import: Option<PathBuf>,
+
+    #[arg(long, help = "Save the scan to a snapshot file when it completes")]
+    pub save: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["PATH", "import", "save"],
+        help = "Load a saved snapshot instead of scanning"
+    )]
+    pub load: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["import", "load", "output", "no_tui"],
+        help = "Update folders that change after the scan completes"
+    )]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["import", "load", "output", "no_tui"],
+        help = "Rescan after this many seconds since the last scan completed"
+    )]
+    pub refresh: Option<u64>,
+
+    #[arg(long, help = "Show changes since a saved snapshot")]
+    pub diff: Option<PathBuf>,
 }
 
 impl Args {
+    /// Report format when running without the Tui.
+    pub fn report_format(&self) -> Option<OutputFormat> {
+        self.output.or(self.no_tui.then_some(OutputFormat::Json))
+    }
+
     /// Consume args and produce [`Vec<Filter>`].
     pub fn filters(self) -> Vec<Filter> {
         self.filter
             .into_iter()
+            .chain(self.regex)
+            .chain(self.contains)
             .chain(self.extension_filter)
+            .chain(self.min_size)
+            .chain(self.max_size)
+            .chain(self.older_than)
+            .chain(self.newer_than)
             .collect()
     }
 }
+
+/// Parse a size in bytes such as `512`, `4K` or `1.5G`. Units are powers
+/// of 1024 and may be written as `M`, `MB` or `MiB`, matching the sizes
+/// displayed.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let invalid = || format!("expected a size like 100M, got {s}");
+    let digits = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let count = s[..digits].parse::<f64>().map_err(|_| invalid())?;
+    let unit = s[digits..].trim_start();
+    let unit = unit
+        .strip_suffix("iB")
+        .or_else(|| unit.strip_suffix('B'))
+        .unwrap_or(unit);
+    let power = match unit.to_ascii_uppercase().as_str() {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return Err(invalid()),
+    };
+    let size = count * 1024_f64.powi(power);
+    if size > u64::MAX as f64 {
+        return Err(format!("size {s} is too large"));
+    }
+    Ok(size as u64)
+}
+
+/// Parse an age before now such as `90d`, `12h`, `2w` or `1y`, or a
+/// `YYYY-MM-DD` date at local midnight.
+fn parse_time(s: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return date
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or_else(|| format!("{s} is not a valid local time"));
+    }
+    let invalid = || format!("expected an age like 90d or a date like 2026-01-01, got {s}");
+    let Some((index, unit)) = s.char_indices().last() else {
+        return Err(invalid());
+    };
+    let count = s[..index]
+        .parse::<i64>()
+        .ok()
+        .filter(|count| *count >= 0)
+        .ok_or_else(invalid)?;
+    let age = match unit {
+        'h' => TimeDelta::try_hours(count),
+        'd' => TimeDelta::try_days(count),
+        'w' => TimeDelta::try_weeks(count),
+        'y' => TimeDelta::try_days(count.saturating_mul(365)),
+        _ => return Err(format!("unknown age unit {unit}, use h, d, w or y")),
+    };
+    age.and_then(|age| Local::now().checked_sub_signed(age))
+        .ok_or_else(|| format!("age {s} is too large"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_size, parse_time};
+
+    #[test]
+    fn test_parse_filter_values() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("1.5MiB"), Ok(1_572_864));
+        assert_eq!(parse_size("2 GB"), Ok(2 << 30));
+        assert!(parse_size("4X").is_err());
+        assert!(parse_size("").is_err());
+
+        assert!(parse_time("90d").is_ok());
+        assert!(parse_time("2026-01-01").is_ok());
+        assert!(parse_time("").is_err());
+        assert!(parse_time("-5d").is_err());
+        assert!(parse_time("300000y").is_err());
+    }
+}
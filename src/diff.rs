@@ -0,0 +1,168 @@
+//! Compare a scan with a baseline scan loaded from a snapshot.
+use crate::{
+    app::FolderStat,
+    snapshot,
+    tree::{FolderKind, FolderTree},
+};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+
+/// Change in a folder's stats from the baseline scan.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FolderDelta {
+    /// Change in total file sizes.
+    pub size: i64,
+    /// Change in total disk usage.
+    pub disk_size: i64,
+    /// Change in total file count.
+    pub files: i64,
+}
+
+impl FolderDelta {
+    /// Change in the size used for bars and sorting.
+    pub fn measured_size(&self, disk_usage: bool) -> i64 {
+        if disk_usage {
+            self.disk_size
+        } else {
+            self.size
+        }
+    }
+}
+
+/// Scan to compare against.
+#[derive(Debug)]
+pub struct Baseline {
+    /// Snapshot file the baseline was loaded from.
+    pub path: PathBuf,
+    /// When the baseline was scanned.
+    pub scanned_at: DateTime<Local>,
+    /// Folders scanned for the baseline.
+    pub root_paths: Vec<PathBuf>,
+    /// Scanned folder hierarchy.
+    pub tree: FolderTree,
+}
+
+impl Baseline {
+    /// Load a baseline from a snapshot file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let snapshot = snapshot::load(path)?;
+        let mut tree = FolderTree::from_grouped_stats(&snapshot.config.groups(), snapshot.folders);
+        for (name, kind) in &snapshot.marked {
+            tree.mark(name, *kind);
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            scanned_at: snapshot.timestamp,
+            root_paths: snapshot.config.root_paths,
+            tree,
+        })
+    }
+
+    /// Fail unless the baseline scanned the same folders as `root_paths`.
+    /// Folders are compared by name relative to the scanned folder, so
+    /// scans of other folders would pair unrelated folders.
+    pub fn check_roots(&self, root_paths: &[PathBuf]) -> Result<()> {
+        let mut expected = self.root_paths.iter().collect::<Vec<_>>();
+        let mut actual = root_paths.iter().collect::<Vec<_>>();
+        expected.sort();
+        actual.sort();
+        if expected != actual {
+            bail!(
+                "{} scanned {}, not {}",
+                self.path.display(),
+                join_paths(&self.root_paths),
+                join_paths(root_paths)
+            );
+        }
+        Ok(())
+    }
+
+    /// Stats of a folder in the baseline scan.
+    pub fn stats(&self, name: &str) -> Option<FolderStat> {
+        self.tree
+            .find(name)
+            .map(|index| self.tree.node(index).stats)
+    }
+
+    /// True if the folder was not in the baseline scan.
+    pub fn is_new(&self, name: &str) -> bool {
+        self.tree.find(name).is_none()
+    }
+
+    /// Change from the baseline to the current stats of a folder.
+    pub fn delta(&self, name: &str, stats: FolderStat) -> FolderDelta {
+        let before = self.stats(name).unwrap_or_default();
+        FolderDelta {
+            size: stats.size as i64 - before.size as i64,
+            disk_size: stats.disk_size as i64 - before.disk_size as i64,
+            files: stats.files as i64 - before.files as i64,
+        }
+    }
+
+    /// Add the folders of the baseline missing from `tree` marked as deleted.
+    pub fn mark_deleted(&self, tree: &mut FolderTree) {
+        for (name, _) in self.tree.folder_files() {
+            if tree.find(name).is_none() {
+                tree.mark(name, FolderKind::Deleted);
+            }
+        }
+    }
+}
+
+/// Paths joined for messages.
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Baseline, FolderDelta};
+    use crate::{
+        app::FolderStat,
+        tree::{FolderKind, FolderTree},
+    };
+    use chrono::Local;
+    use std::path::PathBuf;
+
+    fn stat(size: u64, files: usize) -> FolderStat {
+        FolderStat {
+            size,
+            disk_size: size,
+            files,
+        }
+    }
+
+    #[test]
+    fn test_folder_delta() {
+        let baseline = Baseline {
+            path: PathBuf::new(),
+            scanned_at: Local::now(),
+            root_paths: vec![PathBuf::from("/data")],
+            tree: FolderTree::from_stats([("/a", stat(10, 2)), ("/old/logs", stat(5, 1))]),
+        };
+        let mut tree = FolderTree::from_stats([("/a", stat(4, 1)), ("/new", stat(7, 1))]);
+        baseline.mark_deleted(&mut tree);
+
+        assert_eq!(
+            baseline.delta("/a", tree.node(tree.find("/a").unwrap()).stats),
+            FolderDelta {
+                size: -6,
+                disk_size: -6,
+                files: -1,
+            }
+        );
+        assert_eq!(baseline.delta("/new", stat(7, 1)).size, 7);
+        assert!(baseline.is_new("/new"));
+        let deleted = tree.node(tree.find("/old/logs").unwrap());
+        assert_eq!(deleted.kind, FolderKind::Deleted);
+        assert_eq!(deleted.stats.size, 0);
+
+        assert!(baseline.check_roots(&[PathBuf::from("/data")]).is_ok());
+        assert!(baseline.check_roots(&[PathBuf::from("/other")]).is_err());
+    }
+}
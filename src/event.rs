@@ -1,4 +1,10 @@
-use crate::app::FolderStat;
+use crate::{
+    app::FolderStat,
+    largest::LargeFile,
+    tree::FolderKind,
+    walker::{ScanProgress, WalkError},
+    watch::FolderUpdate,
+};
 use anyhow::Result;
 use crossterm::event::{self, KeyEvent, MouseEvent};
 use log::error;
@@ -25,14 +31,28 @@ pub enum Event {
     Mouse(MouseEvent),
     /// Crossterm resize event.
     Resize(u16, u16),
-    /// Walker scan progress. Emits folder being scanned.
-    Progress(String),
-    /// Walker scan completed.
-    ScanComplete(Duration),
+    /// Walker scan progress counters for a scan generation.
+    Progress(u64, ScanProgress),
+    /// Walker scan completed for a scan generation with the final counters.
+    ScanComplete(u64, ScanProgress),
     /// Initial rendered content frame size.
     ContentFrameSize(u16),
-    /// Walker parallel worker folder collection.
-    FolderEvent(HashMap<String, FolderStat>),
+    /// Walker parallel worker folder collection for a scan generation.
+    FolderEvent(u64, HashMap<String, FolderStat>),
+    /// Walker found a folder to mark, such as a skipped mount point, for a scan generation.
+    MarkFolder(u64, String, FolderKind),
+    /// Walker parallel worker errors for a scan generation.
+    WalkErrors(u64, Vec<WalkError>),
+    /// Entry changed below the scanned folder after a scan generation completed.
+    FolderChanged(u64, String),
+    /// Watching the scanned folders failed for a scan generation.
+    WatchError(u64, String),
+    /// Changed folders rescanned after a scan generation completed.
+    FoldersRescanned(u64, Vec<FolderUpdate>),
+    /// Walker parallel worker largest files for a scan generation.
+    LargestFiles(u64, Vec<LargeFile>),
+    /// Largest files inside a folder after a scan generation completed.
+    FolderLargestFiles(u64, String, Vec<LargeFile>),
 }
 
 /// Application event handler.
@@ -0,0 +1,143 @@
+//! Largest files found while walking, kept in a bounded heap so only the
+//! files that can still make the list are held in memory.
+use std::{cmp::Reverse, collections::BinaryHeap, time::SystemTime};
+
+/// Number of largest files kept for a scan or a folder.
+pub const LARGEST_FILES: usize = 20;
+
+/// A file found by the walker.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LargeFile {
+    /// File size in bytes.
+    pub size: u64,
+    /// Relative name of the file.
+    pub name: String,
+    /// When the file was last modified.
+    pub modified: Option<SystemTime>,
+}
+
+/// The largest files pushed, up to a limit.
+#[derive(Debug, Clone)]
+pub struct LargestFiles {
+    limit: usize,
+    /// Min heap so the smallest kept file is dropped first.
+    heap: BinaryHeap<Reverse<LargeFile>>,
+}
+
+impl Default for LargestFiles {
+    fn default() -> Self {
+        Self::new(LARGEST_FILES)
+    }
+}
+
+impl LargestFiles {
+    /// Keep up to `limit` files.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit + 1),
+        }
+    }
+
+    /// True if a file of `size` bytes would be kept.
+    pub fn fits(&self, size: u64) -> bool {
+        self.heap.len() < self.limit || self.heap.peek().is_some_and(|Reverse(min)| size > min.size)
+    }
+
+    /// Add the file built by `file` if a file of `size` bytes would be
+    /// kept, building it only when needed.
+    pub fn push_with(&mut self, size: u64, file: impl FnOnce() -> LargeFile) {
+        if self.fits(size) {
+            self.push(file());
+        }
+    }
+
+    /// Add a file, dropping the smallest file past the limit.
+    pub fn push(&mut self, file: LargeFile) {
+        self.heap.push(Reverse(file));
+        if self.heap.len() > self.limit {
+            self.heap.pop();
+        }
+    }
+
+    /// True if no files were kept.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Remove all files.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// Kept files from largest to smallest.
+    pub fn to_sorted_vec(&self) -> Vec<LargeFile> {
+        self.heap
+            .clone()
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(file)| file)
+            .collect()
+    }
+
+    /// Kept files inside the folder `name`, from largest to smallest.
+    /// Returns `None` when fewer files than the limit are inside the
+    /// folder, since larger files of the folder may have been dropped.
+    pub fn in_folder(&self, name: &str) -> Option<Vec<LargeFile>> {
+        let files = self
+            .to_sorted_vec()
+            .into_iter()
+            .filter(|file| {
+                file.name
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .collect::<Vec<_>>();
+        (files.len() == self.limit || self.heap.len() < self.limit).then_some(files)
+    }
+}
+
+impl Extend<LargeFile> for LargestFiles {
+    fn extend<T: IntoIterator<Item = LargeFile>>(&mut self, files: T) {
+        for file in files {
+            self.push_with(file.size, || file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LargeFile, LargestFiles};
+
+    fn file(name: &str, size: u64) -> LargeFile {
+        LargeFile {
+            size,
+            name: name.to_owned(),
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn test_largest_files() {
+        let mut largest = LargestFiles::new(2);
+        largest.extend([
+            file("/a/small", 1),
+            file("/a/big", 30),
+            file("/b/medium", 20),
+            file("/ab/tiny", 2),
+        ]);
+        let names = largest
+            .to_sorted_vec()
+            .into_iter()
+            .map(|file| file.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["/a/big", "/b/medium"]);
+        assert!(!largest.fits(20));
+        assert!(largest.in_folder("/a").is_none());
+        assert_eq!(largest.in_folder("").map(|files| files.len()), Some(2));
+
+        let mut all = LargestFiles::new(10);
+        all.extend([file("/a/small", 1), file("/ab/tiny", 2)]);
+        assert_eq!(all.in_folder("/a"), Some(vec![file("/a/small", 1)]));
+    }
+}
@@ -0,0 +1,15 @@
+//! Terminal user interface for viewing folder statistics.
+pub mod app;
+pub mod args;
+pub mod diff;
+pub mod event;
+pub mod largest;
+pub mod ncdu;
+pub mod report;
+pub mod snapshot;
+pub mod tree;
+pub mod tui;
+pub mod ui;
+pub mod update;
+pub mod walker;
+pub mod watch;
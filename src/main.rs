@@ -1,32 +1,47 @@
 use anyhow::Result;
-use app::{App, Config};
-use args::Args;
 use clap::Parser;
-use event::EventHandler;
 use flexi_logger::{FileSpec, Logger};
+use folder_stats_tui::{
+    app::{App, Config},
+    args::Args,
+    diff::Baseline,
+    event::EventHandler,
+    report,
+    tui::Tui,
+    update::handle_event,
+    walker::collect_stats,
+};
 use log::error;
 use ratatui::{backend::CrosstermBackend, Terminal};
-use tui::Tui;
-use update::handle_event;
-use walker::collect_stats;
-
-pub mod app;
-pub mod args;
-pub mod event;
-pub mod tui;
-pub mod ui;
-pub mod update;
-pub mod walker;
 
 fn main() -> Result<()> {
+    let args = Args::parse();
+    let report_format = args.report_format();
+
     Logger::try_with_str("info")?
         .log_to_file(FileSpec::default().basename("f-stats").suffix("log"))
-        .print_message()
+        .o_print_message(report_format.is_none())
         .start()?;
 
-    let args = Args::parse();
+    let (import, load, save) = (args.import.clone(), args.load.clone(), args.save.clone());
+    let baseline = args.diff.as_deref().map(Baseline::load).transpose()?;
+    let mut app = App::new(Config::try_from(args)?);
+    app.save_path = save;
+    app.show_diff = baseline.is_some();
+    app.baseline = baseline;
+    if let Some(path) = &import {
+        app.import_ncdu(path)?;
+    } else if let Some(path) = &load {
+        app.load_snapshot(path)?;
+    }
+    // Loaded scans replace the scanned folders.
+    if let Some(baseline) = &app.baseline {
+        baseline.check_roots(app.config.root_paths)?;
+    }
 
-    let config = Config::try_from(args)?;
+    if let Some(format) = report_format {
+        return report::run(app, format);
+    }
 
     let backend = CrosstermBackend::new(std::io::stderr());
     let terminal = Terminal::new(backend)?;
@@ -34,8 +49,6 @@ fn main() -> Result<()> {
     let sender = event_handler.sender();
     let mut tui = Tui::new(terminal, event_handler)?;
 
-    let mut app = App::new(config);
-
     tui.enter()?;
 
     // Draw the initial screen
@@ -43,7 +56,9 @@ fn main() -> Result<()> {
         error!("Failed to draw tui: {err}");
     }
 
-    collect_stats(sender, config);
+    if app.loaded.is_none() {
+        app.scan = Some(collect_stats(sender, app.config.clone()));
+    }
 
     // Main event loop.
     while !app.should_quit {
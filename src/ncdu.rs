@@ -0,0 +1,249 @@
+//! Read and write the ncdu JSON export format.
+//!
+//! Only folder totals are kept from a scan, so the files directly inside
+//! a folder are written as a single entry with an extra `files` count.
+//! ncdu ignores unknown fields and shows the entry as one file. Folder
+//! entry sizes are not counted on import since the walker does not count
+//! them either.
+use crate::{
+    app::{App, FolderStat},
+    tree::{FolderKind, FolderTree},
+    walker::WalkError,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Major version of the export format.
+const MAJOR_VERSION: u64 = 1;
+
+/// Minor version of the export format.
+const MINOR_VERSION: u64 = 2;
+
+/// Scan loaded from an ncdu export.
+#[derive(Debug, Default)]
+pub struct NcduScan {
+    /// Scanned folder.
+    pub root: PathBuf,
+    /// Stats of the files directly inside each folder keyed by relative name.
+    pub folders: HashMap<String, FolderStat>,
+    /// Folders excluded for being on another file system.
+    pub marked: Vec<(String, FolderKind)>,
+    /// Folders that could not be read.
+    pub errors: Vec<WalkError>,
+    /// When the export was written.
+    pub timestamp: Option<DateTime<Local>>,
+}
+
+/// Load an ncdu export file.
+pub fn import(path: &Path) -> Result<NcduScan> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    read(BufReader::new(file)).with_context(|| format!("Failed to import {}", path.display()))
+}
+
+/// Read an ncdu export.
+pub fn read(reader: impl Read) -> Result<NcduScan> {
+    let value: Value = serde_json::from_reader(reader)?;
+    let Some([major, _minor, metadata, Value::Array(root), ..]) =
+        value.as_array().map(Vec::as_slice)
+    else {
+        bail!("Not an ncdu export");
+    };
+    if major.as_u64() != Some(MAJOR_VERSION) {
+        bail!("Unsupported ncdu export version {major}");
+    }
+    let root_path = root
+        .first()
+        .and_then(|info| info["name"].as_str())
+        .context("Missing root folder name")?;
+
+    let mut importer = Importer {
+        scan: NcduScan {
+            root: PathBuf::from(root_path),
+            timestamp: metadata["timestamp"]
+                .as_i64()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .map(|timestamp| timestamp.with_timezone(&Local)),
+            ..Default::default()
+        },
+        seen: HashSet::new(),
+    };
+    importer.read_dir("", root, 0)?;
+    Ok(importer.scan)
+}
+
+/// State while reading an export.
+struct Importer {
+    scan: NcduScan,
+    /// Device and inode of hard linked files already counted.
+    seen: HashSet<(u64, u64)>,
+}
+
+impl Importer {
+    /// Read a folder entry, an array of the folder info followed by its entries.
+    fn read_dir(&mut self, name: &str, entries: &[Value], parent_dev: u64) -> Result<()> {
+        let (info, children) = entries
+            .split_first()
+            .with_context(|| format!("Empty folder entry in {name:?}"))?;
+        let dev = info["dev"].as_u64().unwrap_or(parent_dev);
+        self.scan.folders.entry(name.to_owned()).or_default();
+        if info["read_error"].as_bool() == Some(true) {
+            self.scan.errors.push(WalkError {
+                path: Some(self.scan.root.join(name.trim_start_matches('/'))),
+                kind: "read error".to_owned(),
+            });
+        }
+
+        for child in children {
+            match child {
+                Value::Array(entries) => {
+                    let child_name = entries
+                        .first()
+                        .and_then(|info| info["name"].as_str())
+                        .with_context(|| format!("Folder without a name in {name:?}"))?;
+                    self.read_dir(&format!("{name}/{child_name}"), entries, dev)?;
+                }
+                Value::Object(_) => self.read_file(name, child, dev),
+                _ => bail!("Unexpected entry in {name:?}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a file entry to the folder it is in.
+    fn read_file(&mut self, folder: &str, info: &Value, dev: u64) {
+        match info["excluded"].as_str() {
+            Some("otherfs" | "othfsexcluded") => {
+                let name = info["name"].as_str().unwrap_or_default();
+                self.scan
+                    .marked
+                    .push((format!("{folder}/{name}"), FolderKind::MountPoint));
+                return;
+            }
+            Some(_) => return,
+            None => (),
+        }
+
+        // Hard linked files are counted once like the walker does.
+        let hard_linked =
+            info["hlnkc"].as_bool() == Some(true) || info["nlink"].as_u64().is_some_and(|n| n > 1);
+        if let Some(ino) = info["ino"].as_u64().filter(|_| hard_linked) {
+            let dev = info["dev"].as_u64().unwrap_or(dev);
+            if !self.seen.insert((dev, ino)) {
+                return;
+            }
+        }
+
+        *self.scan.folders.entry(folder.to_owned()).or_default() += FolderStat {
+            size: info["asize"].as_u64().unwrap_or_default(),
+            disk_size: info["dsize"].as_u64().unwrap_or_default(),
+            files: info["files"].as_u64().map_or(1, |files| files as usize),
+        };
+    }
+}
+
+/// Write the whole scanned hierarchy as an ncdu export.
+pub fn write(app: &App, writer: &mut impl Write) -> Result<()> {
+    if app.config.root_paths.len() > 1 {
+        bail!("ncdu exports hold a single scanned folder");
+    }
+    let timestamp = app.scanned_at.unwrap_or_else(Local::now).timestamp();
+    let metadata = json!({
+        "progname": env!("CARGO_PKG_NAME"),
+        "progver": env!("CARGO_PKG_VERSION"),
+        "timestamp": timestamp,
+    });
+    write!(writer, "[{MAJOR_VERSION},{MINOR_VERSION},{metadata},")?;
+    if app.tree.is_empty() {
+        write!(writer, "[{}]", json!({ "name": app.root_folder() }))?;
+    } else {
+        write_dir(&app.tree, FolderTree::ROOT, &app.root_folder(), writer)?;
+    }
+    writeln!(writer, "]")?;
+    Ok(())
+}
+
+/// Write a folder entry followed by an entry for its files and its sub folders.
+fn write_dir(tree: &FolderTree, index: usize, name: &str, writer: &mut impl Write) -> Result<()> {
+    let node = tree.node(index);
+    if node.kind == FolderKind::MountPoint {
+        write!(writer, "{}", json!({ "name": name, "excluded": "otherfs" }))?;
+        return Ok(());
+    }
+
+    write!(writer, "[{}", json!({ "name": name }))?;
+    let files = tree.direct_stats(index);
+    if files.files > 0 {
+        let entry = json!({
+            "name": format!("[{} files]", files.files),
+            "asize": files.size,
+            "dsize": files.disk_size,
+            "files": files.files,
+        });
+        write!(writer, ",\n{entry}")?;
+    }
+    for &child in &node.children {
+        if tree.node(child).kind == FolderKind::Deleted {
+            continue;
+        }
+        let child_name = tree.node(child).name.rsplit('/').next().unwrap_or_default();
+        writeln!(writer, ",")?;
+        write_dir(tree, child, child_name, writer)?;
+    }
+    write!(writer, "]")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read, write};
+    use crate::{
+        app::{App, Config},
+        args::Args,
+    };
+    use clap::Parser;
+
+    #[test]
+    fn test_read_ncdu_export() {
+        let export = r#"[1,2,{"progname":"ncdu","progver":"1.19","timestamp":1},
+            [{"name":"/data","dev":1},
+             {"name":"a.txt","asize":10,"dsize":4096},
+             [{"name":"logs"},
+              {"name":"b.log","asize":20,"dsize":4096,"ino":5,"hlnkc":true},
+              {"name":"c.log","asize":20,"dsize":4096,"ino":5,"hlnkc":true},
+              {"name":"skipped","excluded":"pattern"}],
+             {"name":"mnt","excluded":"otherfs"}]]"#;
+
+        let scan = read(export.as_bytes()).unwrap();
+        assert_eq!(scan.root.to_str(), Some("/data"));
+        assert_eq!(scan.folders[""].size, 10);
+        assert_eq!(scan.folders["/logs"].files, 1);
+        assert_eq!(scan.folders["/logs"].size, 20);
+        assert_eq!(scan.marked.len(), 1);
+        assert_eq!(scan.marked[0].0, "/mnt");
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let export = r#"[1,2,{},[{"name":"/data"},
+            {"name":"a","asize":1,"dsize":1},
+            {"name":"b","asize":2,"dsize":2},
+            [{"name":"src"},{"name":"c","asize":3,"dsize":3}]]]"#;
+        let scan = read(export.as_bytes()).unwrap();
+        let mut app = App::new(Config::try_from(Args::parse_from(["test"])).unwrap());
+        app.update_scan_result(scan.folders);
+
+        let mut written = Vec::new();
+        write(&app, &mut written).unwrap();
+        let scan = read(written.as_slice()).unwrap();
+        assert_eq!(scan.folders[""].files, 2);
+        assert_eq!(scan.folders[""].size, 3);
+        assert_eq!(scan.folders["/src"].size, 3);
+    }
+}
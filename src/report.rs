@@ -0,0 +1,350 @@
+//! Non interactive scan reports written to stdout.
+use crate::{
+    app::{App, Filter, SortBy},
+    ncdu,
+    update::handle_event,
+    walker::collect_stats,
+};
+use anyhow::{bail, Result};
+use bytesize::ByteSize;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::mpsc,
+};
+
+/// Machine readable report formats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON document with totals, options and folders.
+    Json,
+    /// Comma separated values with one row per folder.
+    Csv,
+    /// Tab separated values with one row per folder.
+    Tsv,
+    /// Markdown table with one row per folder.
+    Markdown,
+    /// ncdu JSON export of the whole scanned hierarchy.
+    Ncdu,
+}
+
+impl OutputFormat {
+    /// Next format when cycling through formats.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Json => Self::Csv,
+            Self::Csv => Self::Tsv,
+            Self::Tsv => Self::Markdown,
+            Self::Markdown => Self::Ncdu,
+            Self::Ncdu => Self::Json,
+        }
+    }
+
+    /// File name extension for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Markdown => "md",
+            Self::Ncdu => "ncdu.json",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Json => "JSON",
+            Self::Csv => "CSV",
+            Self::Tsv => "TSV",
+            Self::Markdown => "Markdown",
+            Self::Ncdu => "ncdu",
+        })
+    }
+}
+
+/// Scan without the Tui unless a scan was loaded, wait for the scan
+/// to complete and write the report to stdout.
+pub fn run(mut app: App, format: OutputFormat) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    if app.loaded.is_none() {
+        app.scan = Some(collect_stats(sender.clone(), app.config.clone()));
+    }
+
+    while app.scanning {
+        let event = receiver.recv()?;
+        handle_event(&mut app, event, sender.clone());
+    }
+    if let Some((false, message)) = &app.status {
+        bail!("{message}");
+    }
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    write_report(&app, format, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the displayed folders in the given format.
+pub fn write_report(app: &App, format: OutputFormat, writer: &mut impl Write) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &JsonReport::new(app))?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Csv => write_delimited(app, ',', writer)?,
+        OutputFormat::Tsv => write_delimited(app, '\t', writer)?,
+        OutputFormat::Markdown => write_markdown(app, writer)?,
+        OutputFormat::Ncdu => ncdu::write(app, writer)?,
+    }
+    Ok(())
+}
+
+/// Write the displayed folders to a file.
+pub fn export(app: &App, format: OutputFormat, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_report(app, format, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a markdown table with one row per displayed folder.
+fn write_markdown(app: &App, writer: &mut impl Write) -> Result<()> {
+    let disk_usage = app.config.disk_usage;
+    let folders = app.report_folders();
+    let total = folders.first().map(|(_, stats)| *stats).unwrap_or_default();
+    let total_size = total.measured_size(disk_usage);
+
+    writeln!(writer, "# Folder stats for {}", app.view_folder())?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "Total size: {}, disk usage: {}, files: {}, folders: {}, depth: {}",
+        ByteSize(total.size),
+        ByteSize(total.disk_size),
+        total.files,
+        folders.len().saturating_sub(1),
+        app.depth
+    )?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "| Folder | Depth | Size | Disk usage | Files | Percent |"
+    )?;
+    writeln!(writer, "|---|---:|---:|---:|---:|---:|")?;
+    for (path, stats) in folders.iter().skip(1) {
+        let percent = match total_size {
+            0 => 0.,
+            total => stats.measured_size(disk_usage) as f64 / total as f64 * 100.,
+        };
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} | {} | {percent:.2}% |",
+            path.replace('|', "\\|"),
+            app.folder_depth(path),
+            ByteSize(stats.size),
+            ByteSize(stats.disk_size),
+            stats.files,
+        )?;
+    }
+    Ok(())
+}
+
+/// Write one row per displayed folder separated by `delimiter`. The human
+/// readable size and the percentage of the root total are for the size
+/// used by the bars.
+fn write_delimited(app: &App, delimiter: char, writer: &mut impl Write) -> Result<()> {
+    let disk_usage = app.config.disk_usage;
+    let folders = app.report_folders();
+    let total_size = folders
+        .first()
+        .map(|(_, stats)| stats.measured_size(disk_usage))
+        .unwrap_or_default();
+
+    let header = [
+        "path",
+        "depth",
+        "size",
+        "human_size",
+        "disk_size",
+        "files",
+        "percent",
+    ];
+    writeln!(writer, "{}", header.join(&delimiter.to_string()))?;
+
+    for (path, stats) in folders.iter().skip(1) {
+        let size = stats.measured_size(disk_usage);
+        let percent = match total_size {
+            0 => 0.,
+            total => size as f64 / total as f64 * 100.,
+        };
+        let row = [
+            escape_field(path, delimiter).into_owned(),
+            app.folder_depth(path).to_string(),
+            stats.size.to_string(),
+            ByteSize(size).to_string(),
+            stats.disk_size.to_string(),
+            stats.files.to_string(),
+            format!("{percent:.2}"),
+        ];
+        writeln!(writer, "{}", row.join(&delimiter.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Escape a field for delimited output. CSV fields are quoted when
+/// needed. TSV fields cannot be quoted so tabs and line breaks are escaped.
+fn escape_field(field: &str, delimiter: char) -> Cow<'_, str> {
+    if delimiter == '\t' {
+        if field.contains(['\t', '\n', '\r', '\\']) {
+            field
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+                .into()
+        } else {
+            field.into()
+        }
+    } else if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// JSON report.
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    root: Cow<'a, str>,
+    view_root: &'a str,
+    scan_time_ms: u128,
+    depth: u8,
+    sort: &'static str,
+    options: JsonOptions<'a>,
+    totals: JsonTotals,
+    folders: Vec<JsonFolder>,
+}
+
+/// Scan options used.
+#[derive(Debug, Serialize)]
+struct JsonOptions<'a> {
+    file_name_filters: Vec<String>,
+    extension_filters: Vec<&'a str>,
+    size_time_filters: Vec<String>,
+    excludes: &'a [String],
+    ignores: bool,
+    show_hidden: bool,
+    disk_usage: bool,
+    dedup: bool,
+    one_file_system: bool,
+    follow_links: bool,
+}
+
+/// Totals for the displayed root folder.
+#[derive(Debug, Serialize)]
+struct JsonTotals {
+    size: u64,
+    disk_size: u64,
+    files: usize,
+    folders: usize,
+    errors: usize,
+    dedup_bytes: u64,
+}
+
+/// Stats for a folder.
+#[derive(Debug, Serialize)]
+struct JsonFolder {
+    path: String,
+    depth: usize,
+    size: u64,
+    disk_size: u64,
+    files: usize,
+}
+
+impl<'a> JsonReport<'a> {
+    fn new(app: &'a App) -> Self {
+        let filters = &app.config.filters;
+        let folders = app.report_folders();
+        let total = folders.first().map(|(_, stats)| *stats).unwrap_or_default();
+
+        Self {
+            root: app.root_folder(),
+            view_root: app.view_root(),
+            scan_time_ms: app.scan_time.as_millis(),
+            depth: app.depth,
+            sort: match app.sort {
+                SortBy::FileSize => "size",
+                SortBy::FileCount => "count",
+            },
+            options: JsonOptions {
+                file_name_filters: filters
+                    .iter()
+                    .filter(|f| f.is_file_name())
+                    .map(ToString::to_string)
+                    .collect(),
+                extension_filters: filters
+                    .iter()
+                    .filter_map(|f| match f {
+                        Filter::Extension(s) => Some(s.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                size_time_filters: filters
+                    .iter()
+                    .filter(|f| f.is_size_or_time())
+                    .map(ToString::to_string)
+                    .collect(),
+                excludes: app.config.excludes,
+                ignores: !app.config.no_ignores,
+                show_hidden: app.config.show_hidden,
+                disk_usage: app.config.disk_usage,
+                dedup: !app.config.no_dedup,
+                one_file_system: app.config.one_file_system,
+                follow_links: app.config.follow_links,
+            },
+            totals: JsonTotals {
+                size: total.size,
+                disk_size: total.disk_size,
+                files: total.files,
+                folders: folders.len().saturating_sub(1),
+                errors: app.walk_errors.len(),
+                dedup_bytes: app.progress.dedup_bytes,
+            },
+            folders: folders
+                .iter()
+                .skip(1)
+                .map(|(path, stats)| JsonFolder {
+                    path: path.clone(),
+                    depth: app.folder_depth(path),
+                    size: stats.size,
+                    disk_size: stats.disk_size,
+                    files: stats.files,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::escape_field;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_field("/src", ','), "/src");
+        assert_eq!(escape_field("/a,b", ','), "\"/a,b\"");
+        assert_eq!(escape_field("/say \"hi\"", ','), "\"/say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_escape_tsv_field() {
+        assert_eq!(escape_field("/a,b", '\t'), "/a,b");
+        assert_eq!(escape_field("/a\tb", '\t'), "/a\\tb");
+    }
+}
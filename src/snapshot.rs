@@ -0,0 +1,185 @@
+//! Complete scans saved to a file and loaded back without walking the
+//! file system.
+use crate::{
+    app::{root_groups, App, Config, Filter, FolderStat},
+    tree::FolderKind,
+    walker::WalkError,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Version of the snapshot file format.
+const SNAPSHOT_VERSION: u32 = 2;
+
+/// A saved scan.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Version of the snapshot file format.
+    pub version: u32,
+    /// When the scan completed.
+    pub timestamp: DateTime<Local>,
+    /// Time taken to scan.
+    pub scan_time: Duration,
+    /// Configuration used for the scan.
+    pub config: SavedConfig,
+    /// Stats of the files directly inside each folder keyed by relative name.
+    pub folders: Vec<(String, FolderStat)>,
+    /// Folders marked by the walker such as skipped mount points.
+    pub marked: Vec<(String, FolderKind)>,
+    /// Errors encountered while walking.
+    pub errors: Vec<WalkError>,
+}
+
+/// Owned copy of the [`Config`] used for a scan.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedConfig {
+    pub root_paths: Vec<PathBuf>,
+    pub filters: Vec<Filter>,
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    pub no_ignores: bool,
+    pub show_hidden: bool,
+    pub disk_usage: bool,
+    pub no_dedup: bool,
+    pub one_file_system: bool,
+    pub follow_links: bool,
+}
+
+impl Snapshot {
+    /// Snapshot of the completed scan.
+    pub fn new(app: &App) -> Self {
+        let config = &app.config;
+        Self {
+            version: SNAPSHOT_VERSION,
+            timestamp: app.scanned_at.unwrap_or_else(Local::now),
+            scan_time: app.scan_time,
+            config: SavedConfig {
+                root_paths: config.root_paths.to_vec(),
+                filters: config.filters.to_vec(),
+                excludes: config.excludes.to_vec(),
+                no_ignores: config.no_ignores,
+                show_hidden: config.show_hidden,
+                disk_usage: config.disk_usage,
+                no_dedup: config.no_dedup,
+                one_file_system: config.one_file_system,
+                follow_links: config.follow_links,
+            },
+            folders: app
+                .tree
+                .folder_files()
+                .map(|(name, stats)| (name.to_owned(), stats))
+                .collect(),
+            marked: app.marked_folders.clone(),
+            errors: app.walk_errors.clone(),
+        }
+    }
+
+    /// Configuration used for the scan, keeping the depth of `config`.
+    pub fn config(&self, config: Config) -> Config {
+        let saved = &self.config;
+        Config {
+            root_paths: Box::leak(saved.root_paths.clone().into_boxed_slice()),
+            filters: saved.filters.clone(),
+            excludes: Box::leak(saved.excludes.clone().into_boxed_slice()),
+            no_ignores: saved.no_ignores,
+            show_hidden: saved.show_hidden,
+            disk_usage: saved.disk_usage,
+            no_dedup: saved.no_dedup,
+            one_file_system: saved.one_file_system,
+            follow_links: saved.follow_links,
+            ..config
+        }
+    }
+}
+
+impl SavedConfig {
+    /// Names of the scanned paths grouped below an unnamed root folder
+    /// when several paths were scanned.
+    pub fn groups(&self) -> Vec<String> {
+        root_groups(&self.root_paths)
+    }
+}
+
+/// Save the completed scan to a file.
+pub fn save(app: &App, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&Snapshot::new(app), &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Load a scan saved to a file.
+pub fn load(path: &Path) -> Result<Snapshot> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    read(BufReader::new(file)).with_context(|| format!("Failed to load {}", path.display()))
+}
+
+/// Write a snapshot.
+pub fn write(snapshot: &Snapshot, writer: &mut impl Write) -> Result<()> {
+    serde_json::to_writer(writer, snapshot)?;
+    Ok(())
+}
+
+/// Read a snapshot.
+pub fn read(reader: impl Read) -> Result<Snapshot> {
+    let snapshot: Snapshot = serde_json::from_reader(reader)?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        bail!("Unsupported snapshot version {}", snapshot.version);
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read, write, Snapshot};
+    use crate::{
+        app::{App, Config, FolderStat},
+        args::Args,
+        tree::FolderKind,
+    };
+    use clap::Parser;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let config = Config::try_from(Args::parse_from(["test", "-e", "rs", "-d", "3"])).unwrap();
+        let mut app = App::new(config.clone());
+        app.marked_folders
+            .push(("/mnt".to_owned(), FolderKind::MountPoint));
+        app.update_scan_result(HashMap::from([
+            (
+                "/src".to_owned(),
+                FolderStat {
+                    size: 10,
+                    disk_size: 4096,
+                    files: 2,
+                },
+            ),
+            ("/src/bin".to_owned(), FolderStat::default()),
+        ]));
+
+        let mut written = Vec::new();
+        write(&Snapshot::new(&app), &mut written).unwrap();
+        let snapshot = read(written.as_slice()).unwrap();
+
+        let folders = snapshot.folders.iter().cloned().collect::<HashMap<_, _>>();
+        assert_eq!(folders["/src"].size, 10);
+        assert_eq!(folders[""].files, 0);
+        assert_eq!(snapshot.marked.len(), 1);
+
+        let loaded = snapshot.config(Config {
+            depth: 1,
+            ..config.clone()
+        });
+        assert_eq!(loaded.root_paths, config.root_paths);
+        assert_eq!(loaded.filters.len(), 1);
+        assert_eq!(loaded.depth, 1);
+    }
+}
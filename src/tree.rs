@@ -0,0 +1,316 @@
+//! In memory folder hierarchy built once from a scan. Changing the
+//! depth, drilling into folders and sorting are views over this tree.
+use crate::app::FolderStat;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A folder in the scanned hierarchy.
+#[derive(Debug, Clone)]
+pub struct FolderNode {
+    /// Folder name relative to the scanned folder. The root is empty.
+    pub name: String,
+    /// Recursive folder stats.
+    pub stats: FolderStat,
+    /// Depth below the scanned folder.
+    pub depth: usize,
+    /// Index of the parent folder.
+    pub parent: Option<usize>,
+    /// Indexes of the child folders.
+    pub children: Vec<usize>,
+    /// Kind of folder.
+    pub kind: FolderKind,
+}
+
+/// Kinds of folders marked in the scanned hierarchy.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FolderKind {
+    /// A regular folder.
+    #[default]
+    Folder,
+    /// Mount point on another file system that was not scanned.
+    MountPoint,
+    /// Folder reached through a symbolic link.
+    Symlink,
+    /// Folder in the baseline scan that no longer exists.
+    Deleted,
+}
+
+/// Scanned folder hierarchy.
+#[derive(Debug, Default, Clone)]
+pub struct FolderTree {
+    nodes: Vec<FolderNode>,
+    index: HashMap<String, usize>,
+    /// Folders named by absolute path placed directly below the root.
+    groups: HashSet<String>,
+}
+
+impl FolderTree {
+    /// Index of the root folder.
+    pub const ROOT: usize = 0;
+
+    /// Build a tree from the stats of files directly inside each folder keyed
+    /// by relative folder name. Stats are rolled up into each ancestor folder.
+    pub fn from_stats<S: AsRef<str>>(stats: impl IntoIterator<Item = (S, FolderStat)>) -> Self {
+        Self::from_grouped_stats(&[], stats)
+    }
+
+    /// Build a tree like [`FolderTree::from_stats`] where the `groups`
+    /// folders are placed directly below an unnamed root folder.
+    pub fn from_grouped_stats<S: AsRef<str>>(
+        groups: &[String],
+        stats: impl IntoIterator<Item = (S, FolderStat)>,
+    ) -> Self {
+        let mut tree = Self {
+            groups: groups.iter().cloned().collect(),
+            ..Default::default()
+        };
+        for group in groups {
+            tree.insert(group);
+        }
+        for (name, stat) in stats {
+            let index = tree.insert(name.as_ref());
+            tree.nodes[index].stats = stat;
+        }
+        tree.roll_up();
+        tree
+    }
+
+    /// Add each folder's stats into its parent. Parents are always
+    /// inserted before their children so a reverse pass visits every
+    /// child before its parent.
+    fn roll_up(&mut self) {
+        for index in (1..self.nodes.len()).rev() {
+            if let Some(parent) = self.nodes[index].parent {
+                let stats = self.nodes[index].stats;
+                self.nodes[parent].stats += stats;
+            }
+        }
+    }
+
+    /// Find or create the folder with the given name and all its ancestors.
+    fn insert(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.index.get(name) {
+            return index;
+        }
+        let parent = if self.groups.contains(name) {
+            Some(self.insert(""))
+        } else {
+            name.rsplit_once('/').map(|(parent, _)| self.insert(parent))
+        };
+        let index = self.nodes.len();
+        self.nodes.push(FolderNode {
+            name: name.to_owned(),
+            stats: FolderStat::default(),
+            depth: parent.map(|p| self.nodes[p].depth + 1).unwrap_or_default(),
+            parent,
+            children: Vec::new(),
+            kind: FolderKind::Folder,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(index);
+        }
+        self.index.insert(name.to_owned(), index);
+        index
+    }
+
+    /// Mark a folder reported by the walker, adding it if it was not scanned.
+    pub fn mark(&mut self, name: &str, kind: FolderKind) {
+        let index = self.insert(name);
+        self.nodes[index].kind = kind;
+    }
+
+    /// Replace the stats of the files directly inside a folder and update
+    /// its ancestors. The folder is added if it is new.
+    pub fn set_folder_files(&mut self, name: &str, files: FolderStat) {
+        let index = self.insert(name);
+        let previous = self.direct_stats(index);
+        let mut next = Some(index);
+        while let Some(index) = next {
+            let node = &mut self.nodes[index];
+            node.stats += files;
+            node.stats -= previous;
+            if node.kind == FolderKind::Deleted {
+                node.kind = FolderKind::Folder;
+            }
+            next = node.parent;
+        }
+    }
+
+    /// Add to the stats of the files directly inside a folder and update
+    /// its ancestors. The folder is added if it is new.
+    pub fn add_folder_files(&mut self, name: &str, files: FolderStat) {
+        let mut next = Some(self.insert(name));
+        while let Some(index) = next {
+            let node = &mut self.nodes[index];
+            node.stats += files;
+            if node.kind == FolderKind::Deleted {
+                node.kind = FolderKind::Folder;
+            }
+            next = node.parent;
+        }
+    }
+
+    /// Mark a folder and its descendants as deleted, removing their
+    /// stats from the ancestors.
+    pub fn remove(&mut self, name: &str) {
+        let Some(index) = self.find(name) else {
+            return;
+        };
+        let stats = self.nodes[index].stats;
+        let mut next = self.nodes[index].parent;
+        while let Some(parent) = next {
+            self.nodes[parent].stats -= stats;
+            next = self.nodes[parent].parent;
+        }
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            let node = &mut self.nodes[index];
+            node.stats = FolderStat::default();
+            node.kind = FolderKind::Deleted;
+            stack.extend_from_slice(&node.children);
+        }
+    }
+
+    /// True if no folders were scanned.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Stats of the files directly inside a folder.
+    pub fn direct_stats(&self, index: usize) -> FolderStat {
+        let node = &self.nodes[index];
+        node.children.iter().fold(node.stats, |mut stats, &child| {
+            stats -= self.nodes[child].stats;
+            stats
+        })
+    }
+
+    /// Each scanned folder's relative name with the stats of the files
+    /// directly inside it.
+    pub fn folder_files(&self) -> impl Iterator<Item = (&str, FolderStat)> + '_ {
+        (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].kind != FolderKind::Deleted)
+            .map(|index| (self.nodes[index].name.as_str(), self.direct_stats(index)))
+    }
+
+    /// Find a folder by its relative name.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// Get a folder by index.
+    pub fn node(&self, index: usize) -> &FolderNode {
+        &self.nodes[index]
+    }
+
+    /// Flatten the folder at `root` followed by all its descendants
+    /// up to `depth` levels below it.
+    pub fn view(&self, root: usize, depth: u8) -> Vec<(String, FolderStat)> {
+        let mut result = Vec::new();
+        if self.nodes.is_empty() {
+            return result;
+        }
+        let max_depth = self.nodes[root].depth + depth as usize;
+        let mut stack = vec![root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            result.push((node.name.clone(), node.stats));
+            if node.depth < max_depth {
+                stack.extend(node.children.iter().rev());
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FolderKind, FolderTree};
+    use crate::app::FolderStat;
+    use std::collections::HashMap;
+
+    fn stat(size: u64) -> FolderStat {
+        FolderStat {
+            size,
+            disk_size: size,
+            files: 1,
+        }
+    }
+
+    #[test]
+    fn test_view_depth() {
+        let tree = FolderTree::from_stats(HashMap::from([
+            ("".to_owned(), stat(4)),
+            ("/a".to_owned(), stat(2)),
+            ("/a/b".to_owned(), stat(2)),
+            ("/a/b/c".to_owned(), stat(2)),
+        ]));
+
+        let names = |depth| {
+            tree.view(FolderTree::ROOT, depth)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(1), ["", "/a"]);
+        assert_eq!(names(3), ["", "/a", "/a/b", "/a/b/c"]);
+
+        let a = tree.find("/a").unwrap();
+        let drilled = tree.view(a, 1);
+        assert_eq!(drilled.len(), 2);
+        assert_eq!(drilled[1].1.size, 4);
+    }
+
+    #[test]
+    fn test_update_folder() {
+        let mut tree =
+            FolderTree::from_stats([("/a", stat(3)), ("/a/b", stat(5)), ("/c", stat(7))]);
+
+        tree.set_folder_files("/a", stat(1));
+        tree.set_folder_files("/a/new", stat(2));
+        tree.remove("/c");
+
+        assert_eq!(tree.node(FolderTree::ROOT).stats.size, 8);
+        assert_eq!(tree.node(tree.find("/a").unwrap()).stats.size, 8);
+        assert_eq!(tree.direct_stats(tree.find("/a").unwrap()).size, 1);
+        let c = tree.node(tree.find("/c").unwrap());
+        assert_eq!((c.kind, c.stats.size), (FolderKind::Deleted, 0));
+
+        tree.add_folder_files("/c/d", stat(4));
+        tree.add_folder_files("/c/d", stat(1));
+        assert_eq!(tree.node(FolderTree::ROOT).stats.size, 13);
+        let c = tree.node(tree.find("/c").unwrap());
+        assert_eq!((c.kind, c.stats.size), (FolderKind::Folder, 5));
+    }
+
+    #[test]
+    fn test_groups() {
+        let groups = ["/data".to_owned(), "/home/user/cache".to_owned()];
+        let tree = FolderTree::from_grouped_stats(
+            &groups,
+            [("/data/a", stat(2)), ("/home/user/cache", stat(3))],
+        );
+
+        let root = tree.node(FolderTree::ROOT);
+        assert_eq!(root.stats.size, 5);
+        assert_eq!(root.children.len(), 2);
+        assert!(tree.find("/home/user").is_none());
+        assert_eq!(tree.node(tree.find("/data/a").unwrap()).depth, 2);
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let tree = FolderTree::from_stats(HashMap::from([
+            ("/a/b".to_owned(), stat(3)),
+            ("/a/c".to_owned(), stat(5)),
+            ("/d".to_owned(), stat(7)),
+        ]));
+
+        let root = tree.node(FolderTree::ROOT);
+        assert_eq!(root.stats.size, 15);
+        assert_eq!(root.stats.files, 3);
+        let a = tree.node(tree.find("/a").unwrap());
+        assert_eq!(a.stats.size, 8);
+        assert_eq!(a.children.len(), 2);
+    }
+}
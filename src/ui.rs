@@ -1,8 +1,12 @@
 use crate::{
-    app::{App, Filter, SortBy},
+    app::{App, Filter, FolderStat, SortBy},
+    diff::FolderDelta,
     event::Event,
+    report::OutputFormat,
+    tree::{FolderKind, FolderTree},
 };
 use bytesize::ByteSize;
+use chrono::{DateTime, Local};
 use log::error;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin},
@@ -19,32 +23,77 @@ use std::{sync::mpsc, time::Duration};
 
 /// Render the Tui based on the [`App`] current state.
 pub fn render(app: &App, frame: &mut Frame, sender: mpsc::Sender<Event>) {
+    // The root entry has the recursive count for both file size and
+    // total files.
+    let total = app.scan_result.first().map(|(_, v)| *v).unwrap_or_default();
+
+    let header = header_lines(app, total);
     let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(6), Constraint::Min(10)])
+        .constraints([
+            Constraint::Length(header.len() as u16 + 2),
+            Constraint::Min(10),
+        ])
         .split(frame.area());
 
-    // Only emit this once on the first render when we have a result.
-    if app.content_height == 0 && !app.scanning {
+    // Emit when the content height changes with the header or terminal size.
+    if app.content_height != rows[1].height.saturating_sub(2) {
         if let Err(err) = sender.send(Event::ContentFrameSize(rows[1].height)) {
             error!("Failed to emit content frame height: {err}");
         }
     }
 
-    // The root entry has the recursive count for both file size and
-    // total files.
-    let (total_size, total_files) = app
-        .scan_result
-        .first()
-        .map(|(_, v)| (v.size, v.files))
-        .unwrap_or_default();
-
-    render_header(app, frame, rows[0], total_files, total_size);
-    render_content(app, frame, rows[1], total_size, total_files);
+    render_header(app, frame, rows[0], header);
+    if app.show_largest {
+        render_largest_files(app, frame, rows[1]);
+    } else {
+        render_content(app, frame, rows[1], total);
+    }
 
     if app.show_help {
         render_help(frame);
     }
+
+    if app.show_errors {
+        render_errors(app, frame);
+    }
+
+    if app.export.is_some() {
+        render_export(app, frame);
+    }
+
+    if app.filter_editor.is_some() {
+        render_filter_editor(app, frame);
+    }
+}
+
+fn render_errors(app: &App, frame: &mut Frame) {
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let block = Block::default()
+        .title(format!("Walk errors ({})", app.walk_errors.len()))
+        .borders(Borders::ALL)
+        .title_alignment(Alignment::Center);
+    let rows = app.walk_errors.iter().skip(app.error_scroll).map(|err| {
+        Row::new(vec![
+            Cell::from(Line::styled(err.kind.as_str(), blue)),
+            Cell::from(Line::styled(
+                err.path
+                    .as_ref()
+                    .map(|p| p.to_string_lossy())
+                    .unwrap_or_default(),
+                red,
+            )),
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Length(20), Constraint::Min(20)])
+        .block(block)
+        .header(Row::new(vec!["Error", "Path"]).bottom_margin(1))
+        .column_spacing(1);
+
+    let area = centered_rect(80, 60, frame.area().height * 6 / 10, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(table, area);
 }
 
 fn render_help(frame: &mut Frame) {
@@ -54,143 +103,509 @@ fn render_help(frame: &mut Frame) {
         .title("Help")
         .borders(Borders::ALL)
         .title_alignment(Alignment::Center);
-    let table = Table::new(
+    let rows = vec![
+        Row::new(vec![
+            Cell::from(Line::styled("1..8", blue)),
+            Cell::from(Line::styled("Change folder depth", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("c", blue)),
+            Cell::from(Line::styled("Sort by file count", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("s", blue)),
+            Cell::from(Line::styled("Sort by file size", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("i", blue)),
+            Cell::from(Line::styled("Toggle ignores", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("h", blue)),
+            Cell::from(Line::styled("Toggle show hidden", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("enter", blue)),
+            Cell::from(Line::styled("Zoom into selected folder", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("backspace", blue)),
+            Cell::from(Line::styled("Back to parent folder", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("x", blue)),
+            Cell::from(Line::styled("Toggle stay on one file system", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("l", blue)),
+            Cell::from(Line::styled("Toggle follow symbolic links", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("u", blue)),
+            Cell::from(Line::styled("Toggle disk usage / apparent size", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("r", blue)),
+            Cell::from(Line::styled("Rescan", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("d", blue)),
+            Cell::from(Line::styled("Toggle changes since snapshot", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("t", blue)),
+            Cell::from(Line::styled("Toggle largest files", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("/ / F", blue)),
+            Cell::from(Line::styled("Edit filters", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("w", blue)),
+            Cell::from(Line::styled("Export displayed folders", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("e", blue)),
+            Cell::from(Line::styled("Toggle walk errors", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("k / up", blue)),
+            Cell::from(Line::styled("Up", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("j / down", blue)),
+            Cell::from(Line::styled("Down", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("pgup / ctrl b / ctrl u", blue)),
+            Cell::from(Line::styled("Page Up", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("pgdn / ctrl d / ctrl f", blue)),
+            Cell::from(Line::styled("Page Down", red)),
+        ]),
+        Row::new(vec![
+            Cell::from(Line::styled("q / ESC", blue)),
+            Cell::from(Line::styled("Quit", red)),
+        ]),
+    ];
+    let table_rows = rows.len() as u16;
+    let table = Table::new(rows, [Constraint::Length(22), Constraint::Percentage(60)])
+        .block(block)
+        .header(Row::new(vec!["Key", "Usage"]).bottom_margin(1))
+        .column_spacing(1);
+
+    let area = centered_rect(50, 50, table_rows + 4, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(table, area);
+}
+
+/// Build the header lines.
+fn header_lines(app: &App, total: FolderStat) -> Vec<Line<'_>> {
+    if app.scanning {
+        let blue = Style::default().light_blue();
+        let red = Style::default().red();
+        let progress = &app.progress;
         vec![
-            Row::new(vec![
-                Cell::from(Line::styled("1..8", blue)),
-                Cell::from(Line::styled("Change folder depth", red)),
-            ]),
-            Row::new(vec![
-                Cell::from(Line::styled("c", blue)),
-                Cell::from(Line::styled("Sort by file count", red)),
-            ]),
-            Row::new(vec![
-                Cell::from(Line::styled("s", blue)),
-                Cell::from(Line::styled("Sort by file size", red)),
+            Line::from(vec![
+                Span::styled("scanning folder: ", blue),
+                Span::styled(app.root_folder(), red),
             ]),
-            Row::new(vec![
-                Cell::from(Line::styled("i", blue)),
-                Cell::from(Line::styled("Toggle ignores", red)),
+            Line::from(vec![
+                Span::styled("Files: ", blue),
+                Span::styled(format!("{} ", progress.files), red),
+                Span::styled("Folders: ", blue),
+                Span::styled(format!("{} ", progress.dirs), red),
+                Span::styled("Size: ", blue),
+                Span::styled(format!("{} ", ByteSize(progress.bytes)), red),
+                Span::styled("Errors: ", blue),
+                Span::styled(format!("{}", progress.errors), red),
             ]),
-            Row::new(vec![
-                Cell::from(Line::styled("h", blue)),
-                Cell::from(Line::styled("Toggle show hidden", red)),
-            ]),
-            Row::new(vec![
-                Cell::from(Line::styled("k / up", blue)),
-                Cell::from(Line::styled("Up", red)),
-            ]),
-            Row::new(vec![
-                Cell::from(Line::styled("j / down", blue)),
-                Cell::from(Line::styled("Down", red)),
+            Line::from(vec![
+                Span::styled("Rate: ", blue),
+                Span::styled(
+                    format!(
+                        "{} files/s {}/s ",
+                        progress.files_per_sec(),
+                        ByteSize(progress.bytes_per_sec())
+                    ),
+                    red,
+                ),
+                Span::styled("Elapsed: ", blue),
+                Span::styled(format_duration(progress.elapsed), red),
             ]),
-            Row::new(vec![
-                Cell::from(Line::styled("pgup / ctrl b / ctrl u", blue)),
-                Cell::from(Line::styled("Page Up", red)),
+            Line::from(vec![
+                Span::styled("Current: ", blue),
+                Span::styled(&progress.current_path, red),
             ]),
-            Row::new(vec![
-                Cell::from(Line::styled("pgdn / ctrl d / ctrl f", blue)),
-                Cell::from(Line::styled("Page Down", red)),
+        ]
+    } else {
+        let blue = Style::default().light_blue();
+        let red = Style::default().red();
+        let mut lines = vec![
+            Line::from(
+                [
+                    Span::styled("Scan results for: ", blue),
+                    Span::styled(app.root_folder(), red),
+                ]
+                .into_iter()
+                .chain(app.view_path.iter().flat_map(|folder| {
+                    [
+                        Span::styled(" > ", blue),
+                        Span::styled(app.short_name(folder), red),
+                    ]
+                }))
+                .collect::<Vec<_>>(),
+            ),
+            Line::from(
+                vec![
+                    Span::styled("Total Size: ", blue),
+                    Span::styled(format!("{} ", ByteSize(total.size)), red),
+                    Span::styled("Disk Usage: ", blue),
+                    Span::styled(format!("{} ", ByteSize(total.disk_size)), red),
+                    Span::styled("Total folders: ", blue),
+                    Span::styled(format!("{} ", app.scan_result.len().saturating_sub(1)), red),
+                    Span::styled("Total Files: ", blue),
+                    Span::styled(format!("{} ", total.files), red),
+                ]
+                .into_iter()
+                .chain(grand_total_spans(app))
+                .collect::<Vec<_>>(),
+            ),
+            Line::from(vec![
+                Span::styled("Folder depth: ", blue),
+                Span::styled(format!("{} ", &app.depth), red),
+                Span::styled("Filter: ", blue),
+                Span::styled(
+                    format!("{} ", {
+                        let names = app
+                            .config
+                            .filters
+                            .iter()
+                            .filter(|f| f.is_file_name())
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>();
+                        names.as_slice().join(",")
+                    }),
+                    red,
+                ),
+                Span::styled("Extension Filter: ", blue),
+                Span::styled(
+                    format!("{} ", {
+                        let extensions = app
+                            .config
+                            .filters
+                            .iter()
+                            .filter_map(|f| match f {
+                                Filter::Extension(s) => Some(s.as_str()),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>();
+                        extensions.as_slice().join(",")
+                    }),
+                    red,
+                ),
+                Span::styled("ignores: ", blue),
+                Span::styled(format!("{} ", !app.config.no_ignores), red),
+                Span::styled("hidden: ", blue),
+                Span::styled(format!("{} ", !app.config.show_hidden), red),
+                Span::styled("disk usage: ", blue),
+                Span::styled(format!("{} ", app.config.disk_usage), red),
+                Span::styled("one file system: ", blue),
+                Span::styled(format!("{} ", app.config.one_file_system), red),
+                Span::styled("follow links: ", blue),
+                Span::styled(format!("{}", app.config.follow_links), red),
             ]),
-            Row::new(vec![
-                Cell::from(Line::styled("q / ESC", blue)),
-                Cell::from(Line::styled("Quit", red)),
+            Line::from(vec![
+                Span::styled("Scan time: ", blue),
+                Span::styled(format!("{} ", format_duration(app.scan_time)), red),
+                Span::styled("Errors: ", blue),
+                Span::styled(format!("{} ", app.walk_errors.len()), red),
+                Span::styled("Duplicate files skipped: ", blue),
+                Span::styled(
+                    if app.config.no_dedup && !app.config.follow_links {
+                        "off".to_owned()
+                    } else {
+                        format!(
+                            "{} ({} files)",
+                            ByteSize(app.progress.dedup_bytes),
+                            app.progress.dedup_files
+                        )
+                    },
+                    red,
+                ),
+                Span::styled(" Watching: ", blue),
+                Span::styled(if app.watcher.is_some() { "on" } else { "off" }, red),
             ]),
-        ],
-        [Constraint::Length(22), Constraint::Percentage(60)],
-    )
-    .block(block)
-    .header(Row::new(vec!["Key", "Usage"]).bottom_margin(1))
-    .column_spacing(1);
+        ];
+        lines.extend(excludes_line(app));
+        lines.extend(size_time_filters_line(app));
+        lines.extend(loaded_line(app));
+        lines.extend(refresh_line(app));
+        lines.extend(diff_line(app, total));
+        lines.extend(status_line(app));
+        lines.push(Line::from("? - for help".light_blue()));
+        lines
+    }
+}
+
+/// Grand total of all scanned folders when several folders are scanned.
+fn grand_total_spans(app: &App) -> Vec<Span<'_>> {
+    if app.config.root_paths.len() < 2 || app.tree.is_empty() {
+        return Vec::new();
+    }
+    let total = app.tree.node(FolderTree::ROOT).stats;
+    vec![
+        Span::styled("Grand Total: ", Style::default().light_blue()),
+        Span::styled(
+            format!("{} ({} files)", ByteSize(total.size), total.files),
+            Style::default().red(),
+        ),
+    ]
+}
+
+/// Line listing the exclude patterns.
+fn excludes_line(app: &App) -> Option<Line<'_>> {
+    (!app.config.excludes.is_empty()).then(|| {
+        Line::from(vec![
+            Span::styled("Excludes: ", Style::default().light_blue()),
+            Span::styled(app.config.excludes.join(","), Style::default().red()),
+        ])
+    })
+}
+
+/// Line for the active file size and modification time filters.
+fn size_time_filters_line(app: &App) -> Option<Line<'_>> {
+    let filters = app
+        .config
+        .filters
+        .iter()
+        .filter(|f| f.is_size_or_time())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    (!filters.is_empty()).then(|| {
+        Line::from(vec![
+            Span::styled("Size/Time Filter: ", Style::default().light_blue()),
+            Span::styled(filters.join(","), Style::default().red()),
+        ])
+    })
+}
+
+/// Line for the file a scan was loaded from and when it was scanned.
+fn loaded_line(app: &App) -> Option<Line<'_>> {
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    app.loaded.as_ref().map(|path| {
+        let mut spans = vec![
+            Span::styled("Loaded: ", blue),
+            Span::styled(format!("{} ", path.display()), red),
+        ];
+        if let Some(scanned_at) = app.scanned_at {
+            spans.push(Span::styled("Scanned at: ", blue));
+            spans.push(Span::styled(
+                scanned_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                red,
+            ));
+        }
+        Line::from(spans)
+    })
+}
+
+/// Line for when a live scan was last refreshed and the total of the
+/// scan before it.
+fn refresh_line(app: &App) -> Option<Line<'_>> {
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let scanned_at = app.scanned_at.filter(|_| app.loaded.is_none())?;
+    let mut spans = vec![
+        Span::styled("Last refresh: ", blue),
+        Span::styled(format!("{} ", scanned_at.format("%H:%M:%S")), red),
+        Span::styled("Auto refresh: ", blue),
+        Span::styled(
+            match app.config.refresh {
+                Some(interval) => format!("every {}s ", interval.as_secs()),
+                None => "off ".to_owned(),
+            },
+            red,
+        ),
+    ];
+    if let Some(previous) = app.previous_total {
+        spans.push(Span::styled("Previous total: ", blue));
+        spans.push(Span::styled(
+            format!(
+                "{} ({} files)",
+                ByteSize(previous.measured_size(app.config.disk_usage)),
+                previous.files
+            ),
+            red,
+        ));
+    }
+    Some(Line::from(spans))
+}
 
-    let area = centered_rect(50, 50, frame.area());
+/// Line for the baseline scan and the change of the displayed root
+/// folder in the diff view.
+fn diff_line(app: &App, total: FolderStat) -> Option<Line<'_>> {
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let baseline = app.baseline.as_ref().filter(|_| app.show_diff)?;
+    let delta = baseline.delta(app.view_root(), total);
+    Some(Line::from(vec![
+        Span::styled("Compared with: ", blue),
+        Span::styled(format!("{} ", baseline.path.display()), red),
+        Span::styled("Scanned at: ", blue),
+        Span::styled(
+            format!("{} ", baseline.scanned_at.format("%Y-%m-%d %H:%M:%S")),
+            red,
+        ),
+        Span::styled("Size change: ", blue),
+        Span::styled(
+            format!(
+                "{}{} ",
+                if delta.size < 0 { "-" } else { "+" },
+                ByteSize(delta.size.unsigned_abs())
+            ),
+            red,
+        ),
+        Span::styled("Files change: ", blue),
+        Span::styled(format!("{:+}", delta.files), red),
+    ]))
+}
+
+/// Line for the outcome of the last action.
+fn status_line(app: &App) -> Option<Line<'_>> {
+    app.status.as_ref().map(|(ok, message)| {
+        Line::styled(
+            message.as_str(),
+            if *ok {
+                Style::default().green()
+            } else {
+                Style::default().red().bold()
+            },
+        )
+    })
+}
+
+fn render_export(app: &App, frame: &mut Frame) {
+    let Some(prompt) = &app.export else {
+        return;
+    };
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let formats = [
+        OutputFormat::Json,
+        OutputFormat::Csv,
+        OutputFormat::Tsv,
+        OutputFormat::Markdown,
+        OutputFormat::Ncdu,
+    ]
+    .into_iter()
+    .flat_map(|format| {
+        [
+            if format == prompt.format {
+                Span::styled(format!("[{format}]"), Style::default().black().on_red())
+            } else {
+                Span::styled(format!(" {format} "), red)
+            },
+            Span::raw(" "),
+        ]
+    });
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("File: ", blue),
+            Span::styled(format!("{}_", prompt.file_name), red),
+        ]),
+        Line::from(
+            [Span::styled("Format: ", blue)]
+                .into_iter()
+                .chain(formats)
+                .collect::<Vec<_>>(),
+        ),
+        Line::from(""),
+        Line::from("tab - change format, enter - save, esc - cancel".light_blue()),
+    ];
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title("Export view")
+            .borders(Borders::ALL)
+            .title_alignment(Alignment::Center),
+    );
+
+    let area = centered_rect(60, 50, 6, frame.area());
     frame.render_widget(Clear, area);
-    frame.render_widget(table, area);
+    frame.render_widget(paragraph, area);
 }
 
-fn render_header(
-    app: &App,
-    frame: &mut Frame,
-    row: ratatui::prelude::Rect,
-    total_files: usize,
-    total_size: u64,
-) {
-    frame.render_widget(
-        Paragraph::new(if app.scanning {
-            vec![Line::from(vec![
-                Span::raw("scanning folder: "),
-                Span::styled(&app.folder_name, Style::default().red()),
-            ])]
-        } else {
-            let blue = Style::default().light_blue();
-            let red = Style::default().red();
-            vec![
-                Line::from(vec![
-                    Span::styled("Scan results for: ", blue),
-                    Span::styled(app.root_folder(), red),
-                ]),
-                Line::from(vec![
-                    Span::styled("Total Size: ", blue),
-                    Span::styled(format!("{} ", ByteSize(total_size)), red),
-                    Span::styled("Total folders: ", blue),
-                    Span::styled(
-                        format!(
-                            "{} ",
-                            app.scan_result.len().checked_sub(1).unwrap_or_default()
-                        ),
-                        red,
-                    ),
-                    Span::styled("Total Files: ", blue),
-                    Span::styled(format!("{total_files} "), red),
-                ]),
-                Line::from(vec![
-                    Span::styled("Folder depth: ", blue),
-                    Span::styled(format!("{} ", &app.depth), red),
-                    Span::styled("Filter: ", blue),
-                    Span::styled(
-                        format!("{} ", {
-                            let names = app
-                                .config
-                                .filters
-                                .iter()
-                                .filter_map(|f| match f {
-                                    Filter::FileName(s) => Some(s.as_str()),
-                                    Filter::Extension(_) => None,
-                                })
-                                .collect::<Vec<_>>();
-                            names.as_slice().join(",")
-                        }),
-                        red,
-                    ),
-                    Span::styled("Extension Filter: ", blue),
-                    Span::styled(
-                        format!("{} ", {
-                            let extensions = app
-                                .config
-                                .filters
-                                .iter()
-                                .filter_map(|f| match f {
-                                    Filter::FileName(_) => None,
-                                    Filter::Extension(s) => Some(s.as_str()),
-                                })
-                                .collect::<Vec<_>>();
-                            extensions.as_slice().join(",")
-                        }),
-                        red,
-                    ),
-                    Span::styled("ignores: ", blue),
-                    Span::styled(format!("{} ", !app.config.no_ignores), red),
-                    Span::styled("hidden: ", blue),
-                    Span::styled(format!("{}", !app.config.show_hidden), red),
-                ]),
-                Line::from(vec![
-                    Span::styled("Scan time: ", blue),
-                    Span::styled(format_duration(app.scan_time), red),
-                ]),
-                Line::from("? - for help".light_blue()),
-            ]
+fn render_filter_editor(app: &App, frame: &mut Frame) {
+    let Some(editor) = &app.filter_editor else {
+        return;
+    };
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let selected = Style::default().black().on_red();
+    let mut lines = editor
+        .filters
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| {
+            let (kind, text) = match filter {
+                Filter::Extension(s) => ("extension", s.clone()),
+                filter => ("file name", filter.to_string()),
+            };
+            Line::from(vec![
+                Span::styled(format!("{kind}: "), blue),
+                Span::styled(
+                    text,
+                    if index == editor.selected {
+                        selected
+                    } else {
+                        red
+                    },
+                ),
+            ])
         })
-        .block(
+        .collect::<Vec<_>>();
+    if editor.filters.is_empty() {
+        lines.push(Line::styled("No filters", red));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            if editor.selected < editor.filters.len() {
+                format!("Change {}: ", editor.kind)
+            } else {
+                format!("Add {}: ", editor.kind)
+            },
+            blue,
+        ),
+        Span::styled(format!("{}_", editor.input), red),
+    ]));
+    if let Some(err) = &editor.error {
+        lines.push(Line::styled(err.lines().last().unwrap_or_default(), red));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        "up/down - select, tab - kind, del - remove".light_blue(),
+    ));
+    lines.push(Line::from(
+        "enter - add/change, enter when empty - rescan, esc - cancel".light_blue(),
+    ));
+    let height = lines.len() as u16 + 2;
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title("Filters")
+            .borders(Borders::ALL)
+            .title_alignment(Alignment::Center),
+    );
+
+    let area = centered_rect(60, 50, height, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+fn render_header(app: &App, frame: &mut Frame, row: ratatui::prelude::Rect, lines: Vec<Line>) {
+    frame.render_widget(
+        Paragraph::new(lines).block(
             Block::default()
                 .title(if app.scanning {
                     "Scan progress"
@@ -204,42 +619,135 @@ fn render_header(
         row,
     );
 }
+
+/// Render the largest files inside the displayed root folder.
+fn render_largest_files(app: &App, frame: &mut Frame<'_>, row: ratatui::prelude::Rect) {
+    let blue = Style::default().light_blue();
+    let red = Style::default().red();
+    let block = Block::default()
+        .title(format!("Largest files in {}", app.view_folder()))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    let message = if app.loaded.is_some() {
+        Some("Files are not listed for loaded scans")
+    } else if app.scanning {
+        Some("Scanning...")
+    } else {
+        None
+    };
+    let files = match (message, app.view_largest_files()) {
+        (None, Some(files)) => files,
+        (message, _) => {
+            let paragraph = Paragraph::new(Line::styled(
+                message.unwrap_or("Finding largest files..."),
+                red,
+            ))
+            .block(block);
+            frame.render_widget(paragraph, row);
+            return;
+        }
+    };
+
+    let rows = files.iter().map(|file| {
+        Row::new(vec![
+            Cell::from(Line::styled(ByteSize(file.size).to_string(), red)),
+            Cell::from(Line::styled(
+                file.modified
+                    .map(|modified| {
+                        DateTime::<Local>::from(modified)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+                red,
+            )),
+            Cell::from(Line::styled(file.name.as_str(), red)),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(17),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(vec![
+        Cell::from(Line::styled("Size", blue)),
+        Cell::from(Line::styled("Modified", blue)),
+        Cell::from(Line::styled("Path", blue)),
+    ]))
+    .block(block);
+    frame.render_widget(table, row);
+}
+
 /// Render the content section.
 fn render_content(
     app: &App,
     frame: &mut Frame<'_>,
     row: ratatui::prelude::Rect,
-    total_size: u64,
-    total_files: usize,
+    total: FolderStat,
 ) {
+    let disk_usage = app.config.disk_usage;
+    let total_size = total.measured_size(disk_usage);
     let scrollbar = Scrollbar::default()
         .orientation(ScrollbarOrientation::VerticalRight)
         .begin_symbol(Some("↑"))
         .end_symbol(Some("↓"));
 
+    let baseline = app.baseline.as_ref().filter(|_| app.show_diff);
+    // Changes are scaled to the largest change displayed.
+    let max_change = baseline
+        .map(|baseline| {
+            app.scan_result
+                .iter()
+                .skip(1)
+                .map(|(name, stats)| baseline.delta(name, *stats))
+                .fold((1, 1), |(size, files), delta| {
+                    (
+                        delta.measured_size(disk_usage).unsigned_abs().max(size),
+                        delta.files.unsigned_abs().max(files),
+                    )
+                })
+        })
+        .unwrap_or((1, 1));
+
     let bar_groups = app
         .scan_result
         .iter()
         .enumerate()
         .skip(app.scroll_state + 1)
         .map(|(index, (name, stats))| {
+            if let Some(baseline) = baseline {
+                return BarGroup::default()
+                    .label(folder_label(app, index, name))
+                    .bars(&diff_bars(
+                        baseline.delta(name, *stats),
+                        disk_usage,
+                        max_change,
+                    ));
+            }
             // Get the percentage for each measurement.
-            let bar_file_size = (stats.size as f32 / total_size as f32) * 100.;
-            let bar_file_num = (stats.files as f32 / total_files as f32) * 100.;
+            let size = stats.measured_size(disk_usage);
+            let bar_file_size = (size as f32 / total_size as f32) * 100.;
+            let bar_file_num = (stats.files as f32 / total.files as f32) * 100.;
             let bars = &[
                 Bar::default()
                     .value(bar_file_size as u64)
                     .style(Style::new().red())
                     .value_style(Style::new().black().on_red())
-                    .text_value(format!("{}", ByteSize(stats.size))),
+                    .text_value(format!("{}", ByteSize(size))),
                 Bar::default()
                     .value(bar_file_num as u64)
                     .style(Style::new().magenta())
                     .value_style(Style::new().black().on_magenta())
                     .text_value(format!("{} files", stats.files)),
             ];
-            let name = format!("{index}. {name}");
-            BarGroup::default().label(name.into()).bars(bars)
+            BarGroup::default()
+                .label(folder_label(app, index, name))
+                .bars(bars)
         });
 
     let mut scrollbar_state = ScrollbarState::new(app.max_scroll)
@@ -250,10 +758,15 @@ fn render_content(
         .direction(Direction::Horizontal)
         .block(
             Block::default()
-                .title(if matches!(app.sort, SortBy::FileSize) {
-                    "Largest by Size"
-                } else {
-                    "Largest by File Count"
+                .title(match app.sort {
+                    SortBy::FileSize if baseline.is_some() && disk_usage => {
+                        "Largest change by Disk Usage"
+                    }
+                    SortBy::FileSize if baseline.is_some() => "Largest change by Size",
+                    SortBy::FileCount if baseline.is_some() => "Largest change by File Count",
+                    SortBy::FileSize if disk_usage => "Largest by Disk Usage",
+                    SortBy::FileSize => "Largest by Size",
+                    SortBy::FileCount => "Largest by File Count",
                 })
                 .border_style(Style::default().fg(Color::DarkGray))
                 .borders(Borders::ALL)
@@ -281,12 +794,76 @@ fn render_content(
     );
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+/// Bars for the change in a folder's size and file count scaled to the
+/// largest changes. Growth and shrinkage are drawn in different colors.
+fn diff_bars(delta: FolderDelta, disk_usage: bool, max_change: (u64, u64)) -> [Bar<'static>; 2] {
+    let size = delta.measured_size(disk_usage);
+    let (size_style, size_value_style) = if size >= 0 {
+        (Style::new().red(), Style::new().black().on_red())
+    } else {
+        (Style::new().green(), Style::new().black().on_green())
+    };
+    let (files_style, files_value_style) = if delta.files >= 0 {
+        (Style::new().magenta(), Style::new().black().on_magenta())
+    } else {
+        (Style::new().cyan(), Style::new().black().on_cyan())
+    };
+    [
+        Bar::default()
+            .value(size.unsigned_abs() * 100 / max_change.0)
+            .style(size_style)
+            .value_style(size_value_style)
+            .text_value(format!(
+                "{}{}",
+                if size < 0 { "-" } else { "+" },
+                ByteSize(size.unsigned_abs())
+            )),
+        Bar::default()
+            .value(delta.files.unsigned_abs() * 100 / max_change.1)
+            .style(files_style)
+            .value_style(files_value_style)
+            .text_value(format!("{:+} files", delta.files)),
+    ]
+}
+
+/// Label for a folder in the bar chart. Marks the selected folder,
+/// folders that were not scanned and changes from the baseline scan.
+fn folder_label<'a>(app: &App, index: usize, name: &'a str) -> Line<'a> {
+    let mut label = if index == app.selected {
+        Line::styled(
+            format!("> {index}. {name}"),
+            Style::new().black().on_light_blue().bold(),
+        )
+    } else {
+        Line::from(format!("{index}. {name}"))
+    };
+    match app.folder_kind(name) {
+        Some(FolderKind::MountPoint) => {
+            label.push_span(Span::styled(
+                " [mount point not scanned]",
+                Style::new().yellow(),
+            ));
+        }
+        Some(FolderKind::Symlink) => {
+            label.push_span(Span::styled(" [symlink]", Style::new().cyan()));
+        }
+        Some(FolderKind::Deleted) => {
+            label.push_span(Span::styled(" [deleted]", Style::new().green()));
+        }
+        _ => (),
+    }
+    if app.show_diff && app.baseline.as_ref().is_some_and(|b| b.is_new(name)) {
+        label.push_span(Span::styled(" [new]", Style::new().red()));
+    }
+    label
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, height: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Length(14),
+            Constraint::Length(height),
             Constraint::Percentage((100 - percent_y) / 2),
         ])
         .split(r);
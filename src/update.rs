@@ -1,16 +1,31 @@
 use crate::{
-    app::{App, FolderStat, SortBy},
+    app::{App, ExportPrompt, FilterEditor, SortBy},
     event::Event,
-    walker::collect_stats,
+    report, snapshot,
+    tree::FolderTree,
+    walker::{self, collect_stats},
+    watch,
 };
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
-use std::{cmp::Reverse, sync::mpsc};
+use log::error;
+use std::{path::Path, sync::mpsc};
 
 fn handle_key_event(app: &mut App, key_event: KeyEvent, sender: mpsc::Sender<Event>) {
+    if app.export.is_some() {
+        handle_export_key(app, key_event);
+        return;
+    }
+    if app.filter_editor.is_some() {
+        handle_filter_editor_key(app, key_event, sender);
+        return;
+    }
     match key_event.code {
         KeyCode::Esc | KeyCode::Char('q') => {
             if app.show_help {
                 app.show_help = false;
+            } else if app.show_errors {
+                app.show_errors = false;
             } else {
                 app.quit();
             }
@@ -20,61 +35,282 @@ fn handle_key_event(app: &mut App, key_event: KeyEvent, sender: mpsc::Sender<Eve
         }
         KeyCode::Char('s') => handle_sort(app, SortBy::FileSize),
         KeyCode::Char('c') => handle_sort(app, SortBy::FileCount),
-        KeyCode::Up | KeyCode::Char('k') => app.scroll_up(1),
-        KeyCode::Down | KeyCode::Char('j') => app.scroll_down(1),
-        KeyCode::PageUp => app.scroll_up(app.compute_scroll_page()),
-        KeyCode::PageDown => app.scroll_down(app.compute_scroll_page()),
-        KeyCode::Home => app.scroll_state = 0,
-        KeyCode::End => app.scroll_state = app.max_scroll,
-        KeyCode::Char('1') => handle_depth_change(app, 1, sender),
-        KeyCode::Char('2') => handle_depth_change(app, 2, sender),
-        KeyCode::Char('3') => handle_depth_change(app, 3, sender),
-        KeyCode::Char('4') => handle_depth_change(app, 4, sender),
-        KeyCode::Char('5') => handle_depth_change(app, 5, sender),
-        KeyCode::Char('6') => handle_depth_change(app, 6, sender),
-        KeyCode::Char('7') => handle_depth_change(app, 7, sender),
-        KeyCode::Char('8') => handle_depth_change(app, 8, sender),
+        KeyCode::Up | KeyCode::Char('k') if app.show_errors => {
+            app.error_scroll = app.error_scroll.saturating_sub(1)
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.show_errors => {
+            app.error_scroll = (app.error_scroll + 1).min(app.walk_errors.len().saturating_sub(1))
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.select_up(1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_down(1),
+        KeyCode::PageUp => app.select_up(app.compute_scroll_page()),
+        KeyCode::PageDown => app.select_down(app.compute_scroll_page()),
+        KeyCode::Home => app.select_up(app.scan_result.len()),
+        KeyCode::End => app.select_down(app.scan_result.len()),
+        KeyCode::Enter => handle_drill_down(app, sender),
+        KeyCode::Backspace => handle_drill_up(app, sender),
+        KeyCode::Char('1') => handle_depth_change(app, 1),
+        KeyCode::Char('2') => handle_depth_change(app, 2),
+        KeyCode::Char('3') => handle_depth_change(app, 3),
+        KeyCode::Char('4') => handle_depth_change(app, 4),
+        KeyCode::Char('5') => handle_depth_change(app, 5),
+        KeyCode::Char('6') => handle_depth_change(app, 6),
+        KeyCode::Char('7') => handle_depth_change(app, 7),
+        KeyCode::Char('8') => handle_depth_change(app, 8),
         KeyCode::Char('?') => app.show_help = !app.show_help,
+        KeyCode::Char('w') => {
+            app.export = Some(ExportPrompt::default());
+            app.status = None;
+        }
+        KeyCode::Char('e') => {
+            app.show_errors = !app.show_errors;
+            app.error_scroll = 0;
+        }
         KeyCode::Char('u') | KeyCode::Char('b') if key_event.modifiers == KeyModifiers::CONTROL => {
-            app.scroll_up(app.compute_scroll_page());
+            app.select_up(app.compute_scroll_page());
         }
         KeyCode::Char('d') | KeyCode::Char('f') if key_event.modifiers == KeyModifiers::CONTROL => {
-            app.scroll_down(app.compute_scroll_page());
+            app.select_down(app.compute_scroll_page());
+        }
+        KeyCode::Char('d') => toggle_diff(app),
+        KeyCode::Char('t') => toggle_largest(app, sender),
+        KeyCode::Char('i')
+        | KeyCode::Char('h')
+        | KeyCode::Char('x')
+        | KeyCode::Char('l')
+        | KeyCode::Char('r')
+        | KeyCode::Char('/')
+        | KeyCode::Char('F')
+            if app.loaded.is_some() =>
+        {
+            app.status = Some((false, "Loaded scans cannot be rescanned".to_owned()));
+        }
+        KeyCode::Char('r') => rescan(app, sender),
+        KeyCode::Char('/') | KeyCode::Char('F') => {
+            app.filter_editor = Some(FilterEditor::new(&app.config.filters));
+            app.status = None;
         }
         KeyCode::Char('i') => toggle_ignores(app, sender),
         KeyCode::Char('h') => toggle_hidden(app, sender),
+        KeyCode::Char('u') => toggle_disk_usage(app),
+        KeyCode::Char('x') => toggle_one_file_system(app, sender),
+        KeyCode::Char('l') => toggle_follow_links(app, sender),
 
         _ => (),
     }
 }
 
-fn handle_depth_change(app: &mut App, depth: u8, sender: mpsc::Sender<Event>) {
-    if app.scanning {
+fn handle_export_key(app: &mut App, key_event: KeyEvent) {
+    let Some(prompt) = app.export.as_mut() else {
         return;
+    };
+    match key_event.code {
+        KeyCode::Esc => app.export = None,
+        KeyCode::Tab => prompt.next_format(),
+        KeyCode::Backspace => {
+            prompt.file_name.pop();
+        }
+        KeyCode::Char(c)
+            if key_event.modifiers == KeyModifiers::NONE
+                || key_event.modifiers == KeyModifiers::SHIFT =>
+        {
+            prompt.file_name.push(c)
+        }
+        KeyCode::Enter => {
+            let prompt = app.export.take().unwrap_or_default();
+            let path = Path::new(&prompt.file_name);
+            app.status = Some(match report::export(app, prompt.format, path) {
+                Ok(()) => (
+                    true,
+                    format!("Exported {} to {}", prompt.format, prompt.file_name),
+                ),
+                Err(err) => {
+                    error!("Failed to export {}: {err}", prompt.file_name);
+                    (
+                        false,
+                        format!("Failed to export {}: {err}", prompt.file_name),
+                    )
+                }
+            });
+        }
+        _ => (),
     }
-    app.scanning = true;
-    app.config.depth = depth;
+}
+
+fn handle_filter_editor_key(app: &mut App, key_event: KeyEvent, sender: mpsc::Sender<Event>) {
+    let Some(editor) = app.filter_editor.as_mut() else {
+        return;
+    };
+    match key_event.code {
+        KeyCode::Esc => app.filter_editor = None,
+        KeyCode::Tab => editor.kind = editor.kind.next(),
+        KeyCode::Up => editor.select(editor.selected.saturating_sub(1)),
+        KeyCode::Down => editor.select(editor.selected + 1),
+        KeyCode::Delete => editor.remove(),
+        KeyCode::Backspace => {
+            editor.input.pop();
+        }
+        KeyCode::Char(c)
+            if key_event.modifiers == KeyModifiers::NONE
+                || key_event.modifiers == KeyModifiers::SHIFT =>
+        {
+            editor.input.push(c)
+        }
+        KeyCode::Enter if !editor.input.is_empty() => editor.submit(),
+        KeyCode::Enter => {
+            let editor = app.filter_editor.take().unwrap_or_default();
+            apply_filters(app, editor, sender);
+        }
+        _ => (),
+    }
+}
 
-    collect_stats(sender, app.config);
+/// Rescan with the edited file name and extension filters, keeping the
+/// size and time filters.
+fn apply_filters(app: &mut App, editor: FilterEditor, sender: mpsc::Sender<Event>) {
+    let size_time_filters = app
+        .config
+        .filters
+        .iter()
+        .filter(|f| f.is_size_or_time())
+        .cloned()
+        .collect::<Vec<_>>();
+    app.config.filters = editor.filters;
+    app.config.filters.extend(size_time_filters);
+    rescan(app, sender);
 }
 
-fn toggle_ignores(app: &mut App, sender: mpsc::Sender<Event>) {
-    if app.scanning {
+fn start_watching(app: &mut App, sender: mpsc::Sender<Event>, generation: u64) {
+    app.watcher = Some(watch::watch(sender, &app.config, generation));
+}
+
+/// Rescan the folders with changed entries unless a rescan is running.
+fn rescan_changed_folders(app: &mut App, sender: mpsc::Sender<Event>) {
+    let Some(generation) = app.scan.as_ref().map(|scan| scan.generation) else {
         return;
+    };
+    let changed = app.take_changed_folders();
+    if !changed.is_empty() {
+        app.rescanning_folders = true;
+        watch::rescan_folders(sender, app.config.clone(), generation, changed);
     }
+}
+
+fn save_snapshot(app: &mut App, path: &Path) {
+    app.status = Some(match snapshot::save(app, path) {
+        Ok(()) => (true, format!("Saved scan to {}", path.display())),
+        Err(err) => {
+            error!("Failed to save {}: {err}", path.display());
+            (false, format!("Failed to save {}: {err}", path.display()))
+        }
+    });
+}
+
+fn handle_depth_change(app: &mut App, depth: u8) {
+    app.depth = depth;
+    app.refresh_view();
+}
+
+fn handle_drill_down(app: &mut App, sender: mpsc::Sender<Event>) {
+    if app.drill_down() {
+        app.refresh_view();
+        find_largest_files(app, sender);
+    }
+}
+
+fn handle_drill_up(app: &mut App, sender: mpsc::Sender<Event>) {
+    if let Some(folder) = app.drill_up() {
+        app.refresh_view();
+        app.select_folder(&folder);
+        find_largest_files(app, sender);
+    }
+}
+
+fn toggle_largest(app: &mut App, sender: mpsc::Sender<Event>) {
+    app.show_largest = !app.show_largest;
+    find_largest_files(app, sender);
+}
+
+/// Walk the displayed root folder for its largest files when they are
+/// shown and the largest files of the scan may be missing some of them.
+fn find_largest_files(app: &mut App, sender: mpsc::Sender<Event>) {
+    if !app.show_largest
+        || app.scanning
+        || app.loaded.is_some()
+        || app.view_largest_files().is_some()
+    {
+        return;
+    }
+    let Some(generation) = app.scan.as_ref().map(|scan| scan.generation) else {
+        return;
+    };
+    let name = app.view_root().to_owned();
+    app.folder_largest_files = Some((name.clone(), None));
+    walker::collect_largest_files(sender, app.config.clone(), generation, name);
+}
+
+fn toggle_ignores(app: &mut App, sender: mpsc::Sender<Event>) {
     app.config.no_ignores = !app.config.no_ignores;
-    app.scanning = true;
+    rescan(app, sender);
+}
 
-    collect_stats(sender, app.config);
+fn toggle_one_file_system(app: &mut App, sender: mpsc::Sender<Event>) {
+    app.config.one_file_system = !app.config.one_file_system;
+    rescan(app, sender);
 }
 
-fn toggle_hidden(app: &mut App, sender: mpsc::Sender<Event>) {
-    if app.scanning {
+fn toggle_follow_links(app: &mut App, sender: mpsc::Sender<Event>) {
+    app.config.follow_links = !app.config.follow_links;
+    rescan(app, sender);
+}
+
+fn toggle_disk_usage(app: &mut App) {
+    app.config.disk_usage = !app.config.disk_usage;
+    app.sort_scan_result();
+    app.scroll_state = 0;
+    app.selected = 1;
+}
+
+fn toggle_diff(app: &mut App) {
+    if app.baseline.is_none() {
+        app.status = Some((false, "No snapshot to compare with, use --diff".to_owned()));
         return;
     }
-    app.scanning = true;
+    let selected = app.selected_folder().map(ToOwned::to_owned);
+    app.show_diff = !app.show_diff;
+    app.refresh_view();
+    if let Some(folder) = selected {
+        app.select_folder(&folder);
+    }
+}
+
+fn toggle_hidden(app: &mut App, sender: mpsc::Sender<Event>) {
     app.config.show_hidden = !app.config.show_hidden;
-    collect_stats(sender, app.config);
+    rescan(app, sender);
+}
+
+/// Start a new scan with the current configuration, cancelling any
+/// scan in progress and discarding its results.
+fn rescan(app: &mut App, sender: mpsc::Sender<Event>) {
+    if let Some(scan) = app.scan.take() {
+        scan.cancel();
+    }
+    if !app.scanning && !app.tree.is_empty() {
+        app.previous_total = Some(app.tree.node(FolderTree::ROOT).stats);
+    }
+    app.watcher = None;
+    app.changed_folders.clear();
+    app.rescanning_folders = false;
+    app.largest_files.clear();
+    app.folder_largest_files = None;
+    app.folder_events.clear();
+    app.walk_errors.clear();
+    app.marked_folders.clear();
+    app.error_scroll = 0;
+    app.partial_result = false;
+    app.progress = Default::default();
+    app.scanning = true;
+    app.scan = Some(collect_stats(sender, app.config.clone()));
 }
 
 fn handle_mouse_event(app: &mut App, mouse_event: MouseEvent) {
@@ -87,29 +323,32 @@ fn handle_mouse_event(app: &mut App, mouse_event: MouseEvent) {
 
 fn handle_sort(app: &mut App, sort_by: SortBy) {
     app.sort = sort_by;
-    app.scan_result.sort_unstable_by_key(|(_, stats)| {
-        Reverse(match sort_by {
-            SortBy::FileSize => stats.size as usize,
-            SortBy::FileCount => stats.files,
-        })
-    });
+    app.sort_scan_result();
     app.scroll_state = 0;
+    app.selected = 1;
 }
 
 /// Main event handler.
 pub fn handle_event(app: &mut App, event: Event, sender: mpsc::Sender<Event>) {
     match event {
         Event::Key(key_event) => handle_key_event(app, key_event, sender),
-        Event::Progress(folder) => app.update_progress(folder),
-        Event::ScanComplete(elapsed) => {
-            let mut sorted_result = std::mem::take(&mut app.folder_events)
-                .into_iter()
-                .collect::<Vec<_>>();
-            sorted_result.sort_unstable_by_key(|(_, stat)| Reverse(stat.size));
-            app.scan_result = sorted_result;
-            app.compute_max_scroll();
+        Event::Progress(generation, progress) if app.is_current_scan(generation) => {
+            app.update_progress(progress)
+        }
+        Event::ScanComplete(generation, progress) if app.is_current_scan(generation) => {
+            let result = std::mem::take(&mut app.folder_events);
+            app.update_scan_result(result);
             app.scanning = false;
-            app.scan_time = elapsed;
+            app.scan_time = progress.elapsed;
+            app.scanned_at = Some(Local::now());
+            app.update_progress(progress);
+            if let Some(path) = app.save_path.clone() {
+                save_snapshot(app, &path);
+            }
+            find_largest_files(app, sender.clone());
+            if app.config.watch {
+                start_watching(app, sender, generation);
+            }
         }
         Event::Mouse(mouse_event) => handle_mouse_event(app, mouse_event),
         Event::Resize(_, h) => {
@@ -120,20 +359,49 @@ pub fn handle_event(app: &mut App, event: Event, sender: mpsc::Sender<Event>) {
             app.content_height = h.checked_sub(2).unwrap_or(h);
             app.compute_max_scroll()
         }
-        Event::FolderEvent(events) => {
-            for (folder_name, stats) in events {
-                app.folder_events
-                    .entry(folder_name)
-                    .and_modify(|fs: &mut FolderStat| {
-                        fs.size += stats.size;
-                        fs.files += stats.files;
-                    })
-                    .or_insert(FolderStat {
-                        size: stats.size,
-                        files: stats.files,
-                    });
+        Event::MarkFolder(generation, name, kind) if app.is_current_scan(generation) => {
+            app.mark_folder(name, kind)
+        }
+        Event::WalkErrors(generation, errors) if app.is_current_scan(generation) => {
+            app.walk_errors.extend(errors);
+        }
+        Event::Tick if app.scanning && app.partial_result => app.update_partial_result(),
+        Event::Tick if app.refresh_due() => rescan(app, sender),
+        Event::Tick
+            if !app.scanning && !app.rescanning_folders && !app.changed_folders.is_empty() =>
+        {
+            rescan_changed_folders(app, sender)
+        }
+        Event::FoldersRescanned(generation, updates) if app.is_current_scan(generation) => {
+            app.rescanning_folders = false;
+            app.update_changed_folders(updates);
+        }
+        Event::WatchError(generation, err) if app.is_current_scan(generation) => {
+            error!("Failed to watch {}: {err}", app.root_folder());
+            app.watcher = None;
+            app.status = Some((false, format!("Failed to watch for changes: {err}")));
+        }
+        Event::FolderChanged(generation, name)
+            if app.is_current_scan(generation) && !app.scanning =>
+        {
+            // The folder holding the changed entry is rescanned.
+            if let Some((parent, _)) = name.rsplit_once('/') {
+                app.changed_folders.insert(parent.to_owned());
             }
         }
+        Event::LargestFiles(generation, files) if app.is_current_scan(generation) => {
+            app.largest_files.extend(files);
+        }
+        Event::FolderLargestFiles(generation, name, files) if app.is_current_scan(generation) => {
+            if let Some((folder, largest)) = &mut app.folder_largest_files {
+                if *folder == name {
+                    *largest = Some(files);
+                }
+            }
+        }
+        Event::FolderEvent(generation, events) if app.is_current_scan(generation) => {
+            app.add_folder_events(events)
+        }
         _ => (),
     }
 }
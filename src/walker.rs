@@ -5,60 +5,392 @@
 use crate::{
     app::{Config, Filter, FolderStat},
     event::Event,
+    largest::{LargeFile, LargestFiles},
+    tree::FolderKind,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState,
 };
-use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState};
 use log::error;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    os::unix::ffi::OsStrExt,
-    sync::mpsc::Sender,
-    time::{Duration, Instant},
+    collections::{HashMap, HashSet},
+    fs::Metadata,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+/// Source of unique scan generations.
+static SCAN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Number of entries a worker visits before publishing its counters.
+const PROGRESS_BATCH: u64 = 1024;
+
+/// How often scan progress is emitted.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often workers emit partial folder stats.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// State shared by a scan's workers, its progress thread and its handle.
+#[derive(Debug, Default)]
+struct ScanState {
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+    files: AtomicU64,
+    bytes: AtomicU64,
+    dirs: AtomicU64,
+    errors: AtomicU64,
+    dedup_files: AtomicU64,
+    dedup_bytes: AtomicU64,
+    current_path: Mutex<String>,
+    /// Device and inode of visited files with more than one hard link,
+    /// or of all files when following symbolic links.
+    files_seen: Mutex<HashSet<(u64, u64)>>,
+    /// Device and inode of visited folders when following symbolic links.
+    folders_seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+/// Snapshot of the progress of a running scan.
+#[derive(Debug, Clone, Default)]
+pub struct ScanProgress {
+    /// Files visited.
+    pub files: u64,
+    /// Total size of the visited files.
+    pub bytes: u64,
+    /// Folders entered.
+    pub dirs: u64,
+    /// Walk errors.
+    pub errors: u64,
+    /// Hard linked files counted only once.
+    pub dedup_files: u64,
+    /// Size of the hard linked files counted only once.
+    pub dedup_bytes: u64,
+    /// Folder recently visited by a worker.
+    pub current_path: String,
+    /// Time since the scan started.
+    pub elapsed: Duration,
+}
+
+impl ScanProgress {
+    /// Files visited per second.
+    pub fn files_per_sec(&self) -> u64 {
+        self.per_sec(self.files)
+    }
+
+    /// Bytes summed per second.
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.per_sec(self.bytes)
+    }
+
+    fn per_sec(&self, count: u64) -> u64 {
+        match self.elapsed.as_millis() as u64 {
+            0 => 0,
+            ms => count.saturating_mul(1000) / ms,
+        }
+    }
+}
+
+/// Handle to a running scan that can be used to cancel it. Events
+/// emitted by the scan carry its generation.
+#[derive(Debug, Clone)]
+pub struct ScanHandle {
+    /// Generation of the scan.
+    pub generation: u64,
+    state: Arc<ScanState>,
+}
+
+impl ScanHandle {
+    fn new() -> Self {
+        Self {
+            generation: SCAN_GENERATION.fetch_add(1, Ordering::Relaxed),
+            state: Default::default(),
+        }
+    }
+
+    /// Abort the scan. No further results are emitted.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    /// True if the scan was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire)
+    }
+
+    /// True once the walk has ended.
+    fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Acquire)
+    }
+
+    /// Record a visited file or folder. Returns false if it was already visited.
+    fn first_visit(&self, seen: &Mutex<HashSet<(u64, u64)>>, md: &Metadata) -> bool {
+        seen.lock()
+            .map(|mut seen| seen.insert((md.dev(), md.ino())))
+            .unwrap_or(true)
+    }
+
+    /// Take a snapshot of the counters published by the workers.
+    fn progress(&self, start: Instant) -> ScanProgress {
+        ScanProgress {
+            files: self.state.files.load(Ordering::Relaxed),
+            bytes: self.state.bytes.load(Ordering::Relaxed),
+            dirs: self.state.dirs.load(Ordering::Relaxed),
+            errors: self.state.errors.load(Ordering::Relaxed),
+            dedup_files: self.state.dedup_files.load(Ordering::Relaxed),
+            dedup_bytes: self.state.dedup_bytes.load(Ordering::Relaxed),
+            current_path: self
+                .state
+                .current_path
+                .lock()
+                .map(|path| path.clone())
+                .unwrap_or_default(),
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// An error encountered while walking. The scan continues past it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkError {
+    /// Path that could not be read.
+    pub path: Option<PathBuf>,
+    /// Kind of error.
+    pub kind: String,
+}
+
+impl From<&ignore::Error> for WalkError {
+    fn from(err: &ignore::Error) -> Self {
+        Self {
+            path: error_path(err).map(Path::to_path_buf),
+            kind: match err.io_error() {
+                Some(io_err) => io_err.kind().to_string(),
+                None if err.is_partial() => "partial".to_owned(),
+                None => match error_cause(err) {
+                    ignore::Error::Loop { .. } => "file system loop".to_owned(),
+                    cause => cause.to_string(),
+                },
+            },
+        }
+    }
+}
+
+/// Unwrap the path, depth and line number context of an error.
+fn error_cause(err: &ignore::Error) -> &ignore::Error {
+    match err {
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => error_cause(err),
+        _ => err,
+    }
+}
+
+/// Find the path an error is about.
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Partial(errs) => errs.iter().find_map(error_path),
+        _ => None,
+    }
+}
+
+/// Counters collected by a worker that have not yet been published.
+#[derive(Debug, Default)]
+struct PendingProgress {
+    visited: u64,
+    files: u64,
+    bytes: u64,
+    dirs: u64,
+    errors: u64,
+    dedup_files: u64,
+    dedup_bytes: u64,
+}
+
 /// Path visitor for each parallel thread worker.
 struct MyParallelVisitor<'a> {
     root_path_bytes: &'a [u8],
     sender: Sender<Event>,
-    depth: u8,
-    results: HashMap<String, FolderStat>,
+    scan: ScanHandle,
+    /// Count hard linked files only once.
+    dedup: bool,
+    /// Count files reached through symbolic links only once.
+    follow_links: bool,
+    /// Scanned folders, to skip links to files inside them.
+    root_paths: &'a [PathBuf],
+    /// Stats for files directly inside each visited folder. Totals
+    /// are rolled up to ancestor folders when the scan tree is built.
+    results: HashMap<PathBuf, FolderStat>,
+    progress: PendingProgress,
+    /// Walk errors since the last flush.
+    errors: Vec<WalkError>,
+    /// Largest files visited, emitted when the worker finishes.
+    largest: LargestFiles,
+    /// When counters were last published.
+    last_publish: Instant,
+    /// When results were last emitted.
+    last_flush: Instant,
 }
 
 impl MyParallelVisitor<'_> {
+    /// Emit the folder stats collected since the last flush.
+    fn flush_results(&mut self) {
+        self.last_flush = Instant::now();
+        if !self.errors.is_empty() {
+            let errors = std::mem::take(&mut self.errors);
+            if let Err(err) = self
+                .sender
+                .send(Event::WalkErrors(self.scan.generation, errors))
+            {
+                error!("Failed to emit walk errors {err}");
+            }
+        }
+        if self.results.is_empty() {
+            return;
+        }
+        let results = std::mem::take(&mut self.results)
+            .into_iter()
+            .filter(|(path, _)| {
+                path.as_os_str()
+                    .as_bytes()
+                    .starts_with(self.root_path_bytes)
+            })
+            .map(|(path, stats)| (self.truncate_root(&path), stats))
+            .collect();
+        if let Err(err) = self
+            .sender
+            .send(Event::FolderEvent(self.scan.generation, results))
+        {
+            error!("Failed to emit folder events {err}");
+        }
+    }
+
+    /// Get the entry metadata, recording any error.
+    fn metadata(&mut self, entry: &DirEntry) -> Option<Metadata> {
+        entry
+            .metadata()
+            .inspect_err(|err| self.record_error(err))
+            .ok()
+    }
+
+    /// Record a walk error to be emitted with the next flush.
+    fn record_error(&mut self, err: &ignore::Error) {
+        error!("Failed to walk {err}");
+        self.progress.errors += 1;
+        self.errors.push(WalkError::from(err));
+    }
+
+    /// Publish pending counters to the shared scan state.
+    fn publish_progress(&mut self, current_path: Option<&Path>) {
+        let state = &self.scan.state;
+        let progress = std::mem::take(&mut self.progress);
+        state.files.fetch_add(progress.files, Ordering::Relaxed);
+        state.bytes.fetch_add(progress.bytes, Ordering::Relaxed);
+        state.dirs.fetch_add(progress.dirs, Ordering::Relaxed);
+        state.errors.fetch_add(progress.errors, Ordering::Relaxed);
+        state
+            .dedup_files
+            .fetch_add(progress.dedup_files, Ordering::Relaxed);
+        state
+            .dedup_bytes
+            .fetch_add(progress.dedup_bytes, Ordering::Relaxed);
+        if let Some(path) = current_path {
+            if let Ok(mut current) = state.current_path.try_lock() {
+                *current = path.to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    /// Publish counters after a batch of entries, or after the progress
+    /// interval so slow file systems still update, and flush results
+    /// after the flush interval.
+    fn publish_when_due(&mut self, current_path: Option<&Path>) {
+        if self.progress.visited < PROGRESS_BATCH && self.last_publish.elapsed() < PROGRESS_INTERVAL
+        {
+            return;
+        }
+        self.last_publish = Instant::now();
+        self.publish_progress(current_path);
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush_results();
+        }
+    }
+
     /// Convert the canonical path into a relative path.
-    fn truncate_root(&self, path: &str) -> String {
-        path.split_at(self.root_path_bytes.len()).1.to_owned()
+    fn truncate_root(&self, path: &Path) -> String {
+        relative_name(self.root_path_bytes, path)
     }
 }
 
 impl ParallelVisitor for MyParallelVisitor<'_> {
     /// Visit each directory entry.
     fn visit(&mut self, result: Result<DirEntry, ignore::Error>) -> WalkState {
+        if self.scan.is_cancelled() {
+            return WalkState::Quit;
+        }
+        self.progress.visited += 1;
         match result {
             Ok(entry) => {
-                if let Ok(size) = entry.metadata().map(|md| md.len()) {
-                    let parents = entry
-                        .path()
-                        .ancestors()
-                        .skip(entry.depth().checked_sub(self.depth as usize).unwrap_or(1))
-                        .filter(|p| !p.is_symlink() && p.is_dir())
-                        .flat_map(|p| p.as_os_str().to_str())
-                        .take_while(|p| p.as_bytes().starts_with(self.root_path_bytes));
-
-                    for parent in parents {
+                if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                    self.progress.dirs += 1;
+                    if !self.results.contains_key(entry.path()) {
                         self.results
-                            .entry(self.truncate_root(parent))
-                            .and_modify(|fs: &mut FolderStat| {
-                                fs.size += size;
-                                fs.files += 1;
-                            })
-                            .or_insert(FolderStat { size, files: 1 });
+                            .insert(entry.path().to_path_buf(), FolderStat::default());
+                    }
+                } else if let (Some(md), Some(parent)) =
+                    (self.metadata(&entry), entry.path().parent())
+                {
+                    // Files linked from inside the scanned folders are
+                    // counted at their real path.
+                    if (self.follow_links
+                        && entry.path_is_symlink()
+                        && links_into_roots(self.root_paths, entry.path()))
+                        || (((self.dedup && md.nlink() > 1) || self.follow_links)
+                            && !self.scan.first_visit(&self.scan.state.files_seen, &md))
+                    {
+                        self.progress.dedup_files += 1;
+                        self.progress.dedup_bytes += md.len();
+                        return WalkState::Continue;
+                    }
+                    let stats = FolderStat {
+                        size: md.len(),
+                        disk_size: md.blocks() * 512,
+                        files: 1,
+                    };
+                    self.progress.files += 1;
+                    self.progress.bytes += stats.size;
+                    let root_path_bytes = self.root_path_bytes;
+                    self.largest.push_with(stats.size, || LargeFile {
+                        size: stats.size,
+                        name: relative_name(root_path_bytes, entry.path()),
+                        modified: md.modified().ok(),
+                    });
+                    match self.results.get_mut(parent) {
+                        Some(fs) => *fs += stats,
+                        None => {
+                            self.results.insert(parent.to_path_buf(), stats);
+                        }
                     }
                 }
+                self.publish_when_due(entry.path().parent());
                 WalkState::Continue
             }
             Err(err) => {
-                error!("Failed to walk {err}");
-                WalkState::Quit
+                self.record_error(&err);
+                self.publish_when_due(None);
+                WalkState::Continue
             }
         }
     }
@@ -66,9 +398,78 @@ impl ParallelVisitor for MyParallelVisitor<'_> {
 
 impl Drop for MyParallelVisitor<'_> {
     fn drop(&mut self) {
-        let results = std::mem::take(&mut self.results);
-        if let Err(err) = self.sender.send(Event::FolderEvent(results)) {
-            error!("Failed to emit folder events {err}");
+        if self.scan.is_cancelled() {
+            return;
+        }
+        self.publish_progress(None);
+        self.flush_results();
+        if !self.largest.is_empty() {
+            let largest = self.largest.to_sorted_vec();
+            if let Err(err) = self
+                .sender
+                .send(Event::LargestFiles(self.scan.generation, largest))
+            {
+                error!("Failed to emit largest files {err}");
+            }
+        }
+    }
+}
+
+/// Convert a canonical path under the scanned folder into a relative path.
+pub(crate) fn relative_name(root_path_bytes: &[u8], path: &Path) -> String {
+    // Keep the leading separator when scanning from the file system root.
+    let root_len = root_path_bytes
+        .strip_suffix(b"/")
+        .unwrap_or(root_path_bytes)
+        .len();
+    match &path.as_os_str().as_bytes()[root_len..] {
+        b"/" => String::new(),
+        relative => String::from_utf8_lossy(relative).into_owned(),
+    }
+}
+
+/// Checks folders before they are walked. Skips folders on another file
+/// system than the scanned folder and folders already reached through a
+/// symbolic link.
+struct FolderFilter {
+    /// Device of each scanned folder when staying on one file system.
+    root_devs: Vec<(&'static Path, u64)>,
+    follow_links: bool,
+    root_paths: &'static [PathBuf],
+    root_path_bytes: &'static [u8],
+    sender: Sender<Event>,
+    scan: ScanHandle,
+}
+
+impl FolderFilter {
+    /// True if the folder should be walked. Symbolic links and skipped
+    /// mount points are emitted so they can be marked. Only links leaving
+    /// the scanned folders are followed, so folders inside them are
+    /// counted at their real path.
+    fn check(&self, entry: &DirEntry) -> bool {
+        if entry.path_is_symlink() {
+            self.mark(entry, FolderKind::Symlink);
+            if self.follow_links && links_into_roots(self.root_paths, entry.path()) {
+                return false;
+            }
+        }
+        let Ok(md) = entry.metadata() else {
+            return true;
+        };
+        if !on_root_device(&self.root_devs, entry.path(), &md) {
+            self.mark(entry, FolderKind::MountPoint);
+            return false;
+        }
+        !self.follow_links || self.scan.first_visit(&self.scan.state.folders_seen, &md)
+    }
+
+    fn mark(&self, entry: &DirEntry, kind: FolderKind) {
+        let name = relative_name(self.root_path_bytes, entry.path());
+        if let Err(err) = self
+            .sender
+            .send(Event::MarkFolder(self.scan.generation, name, kind))
+        {
+            error!("Failed to emit folder mark {err}");
         }
     }
 }
@@ -76,7 +477,10 @@ impl Drop for MyParallelVisitor<'_> {
 /// Parallel visitor builder.
 struct MyVisitorBuilder<'a> {
     sender: Sender<Event>,
-    depth: u8,
+    scan: ScanHandle,
+    dedup: bool,
+    follow_links: bool,
+    root_paths: &'a [PathBuf],
     root_path_bytes: &'a [u8],
     start: Instant,
 }
@@ -86,102 +490,505 @@ impl<'a> ParallelVisitorBuilder<'a> for MyVisitorBuilder<'a> {
     fn build(&mut self) -> Box<dyn ignore::ParallelVisitor + 'a> {
         Box::new(MyParallelVisitor {
             sender: self.sender.clone(),
-            depth: self.depth,
+            scan: self.scan.clone(),
+            dedup: self.dedup,
+            follow_links: self.follow_links,
+            root_paths: self.root_paths,
             root_path_bytes: self.root_path_bytes,
             results: HashMap::new(),
+            progress: PendingProgress::default(),
+            errors: Vec::new(),
+            largest: LargestFiles::default(),
+            last_publish: Instant::now(),
+            last_flush: Instant::now(),
         })
     }
 }
 
 impl Drop for MyVisitorBuilder<'_> {
     fn drop(&mut self) {
-        let elapsed = Instant::now() - self.start;
-        if let Err(err) = self.sender.send(Event::ScanComplete(elapsed)) {
+        self.scan.state.finished.store(true, Ordering::Release);
+        if self.scan.is_cancelled() {
+            return;
+        }
+        if let Err(err) = self.sender.send(Event::ScanComplete(
+            self.scan.generation,
+            self.scan.progress(self.start),
+        )) {
             error!("Failed to emit scan complete {err}");
         }
     }
 }
 
 /// Spawn a thread that will configure and launch the ignore parallel walker. Each
-/// visitor will collect it's results and then emit them when dropped. The builder
-/// emits a traversal completed event when it is dropped.
-pub fn collect_stats(sender: Sender<Event>, config: Config) {
-    start_progress_indicator(&sender, &config);
+/// visitor will periodically emit partial results and then emit the remainder when
+/// dropped. The builder emits a traversal completed event when it is dropped. The
+/// returned handle cancels the walk and suppresses all of its remaining events.
+pub fn collect_stats(sender: Sender<Event>, config: Config) -> ScanHandle {
+    let scan = ScanHandle::new();
+    let handle = scan.clone();
+    start_progress_indicator(&sender, &scan);
+
+    if config.follow_links {
+        // Links back into a scanned folder are not walked again.
+        for root in config.root_paths {
+            match std::fs::metadata(root) {
+                Ok(md) => {
+                    scan.first_visit(&scan.state.folders_seen, &md);
+                }
+                Err(err) => error!("Failed to read root {err}"),
+            }
+        }
+    }
+
+    let folder_filter = (config.one_file_system || config.follow_links).then(|| FolderFilter {
+        root_devs: root_devices(&config),
+        follow_links: config.follow_links,
+        root_paths: config.root_paths,
+        root_path_bytes: config.name_prefix(),
+        sender: sender.clone(),
+        scan: scan.clone(),
+    });
 
     std::thread::spawn(move || {
-        let walker = WalkBuilder::new(config.root_path)
+        let file_filter = FileFilter::new(&config.filters);
+        let walker = walk_builder(config.root_paths, &config)
             .filter_entry(move |entry| {
-                (entry.file_type().map(|e| e.is_file()).unwrap_or(false)
-                    && check_filename_filter(entry, config.filters)
-                    && check_file_extension_filter(entry, config.filters))
-                    || entry.file_type().map(|e| e.is_dir()).unwrap_or(false)
+                file_filter.check(entry)
+                    || (entry.file_type().map(|e| e.is_dir()).unwrap_or(false)
+                        && folder_filter
+                            .as_ref()
+                            .is_none_or(|filter| filter.check(entry)))
             })
-            .ignore(!config.no_ignores)
-            .hidden(!config.show_hidden)
-            .git_ignore(!config.no_ignores)
             .build_parallel();
 
-        let root_path_bytes = config.root_path.as_os_str().as_bytes();
+        let root_path_bytes = config.name_prefix();
 
         let mut my_builder = MyVisitorBuilder {
             sender,
-            depth: config.depth,
+            scan,
+            dedup: !config.no_dedup,
+            follow_links: config.follow_links,
+            root_paths: config.root_paths,
             root_path_bytes,
             start: Instant::now(),
         };
 
         walker.visit(&mut my_builder);
     });
+
+    handle
 }
 
-fn start_progress_indicator(sender: &Sender<Event>, config: &Config) {
-    let sender = sender.clone();
-    let scan_folder = config
-        .root_path
-        .to_str()
-        .map(ToOwned::to_owned)
-        .unwrap_or_default();
+/// Walker for folders with the ignore, hidden and symbolic link options.
+fn walk_builder<P: AsRef<Path>>(paths: &[P], config: &Config) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(&paths[0]);
+    for path in &paths[1..] {
+        builder.add(path);
+    }
+    match exclude_overrides(config) {
+        Ok(overrides) => {
+            builder.overrides(overrides);
+        }
+        Err(err) => error!("Failed to build exclude patterns {err}"),
+    }
+    builder
+        .ignore(!config.no_ignores)
+        .hidden(!config.show_hidden)
+        .git_ignore(!config.no_ignores)
+        .follow_links(config.follow_links);
+    builder
+}
+
+/// Overrides skipping the exclude patterns. Patterns containing a
+/// separator are relative to the scanned folder, or to the file system
+/// root when scanning several folders.
+pub(crate) fn exclude_overrides(config: &Config) -> Result<Override, ignore::Error> {
+    let base = match config.root_paths {
+        [root] => root.as_path(),
+        _ => Path::new("/"),
+    };
+    let mut builder = OverrideBuilder::new(base);
+    for glob in config.excludes {
+        builder.add(&format!("!{glob}"))?;
+    }
+    builder.build()
+}
+
+/// Walk a folder below the scanned folder on the current thread up to
+/// `max_depth` levels, returning the stats of the files directly inside
+/// each visited folder keyed by relative name. Used to update folders
+/// that changed after a scan, so hard linked files are not deduplicated.
+pub fn scan_folder(
+    config: &Config,
+    name: &str,
+    max_depth: Option<usize>,
+) -> HashMap<String, FolderStat> {
+    let mut results = HashMap::new();
+    let Some(path) = config.folder_path(name) else {
+        return results;
+    };
+    let root_path_bytes = config.name_prefix();
+    for result in sequential_walk(config, &path, max_depth) {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => {
+                error!("Failed to walk {err}");
+                continue;
+            }
+        };
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            results
+                .entry(relative_name(root_path_bytes, entry.path()))
+                .or_insert_with(FolderStat::default);
+        } else if let (Ok(md), Some(parent)) = (entry.metadata(), entry.path().parent()) {
+            *results
+                .entry(relative_name(root_path_bytes, parent))
+                .or_insert_with(FolderStat::default) += FolderStat {
+                size: md.len(),
+                disk_size: md.blocks() * 512,
+                files: 1,
+            };
+        }
+    }
+    results
+}
 
+/// Walk the largest files inside a folder below the scanned folder on
+/// another thread, emitting them for a scan generation.
+pub fn collect_largest_files(sender: Sender<Event>, config: Config, generation: u64, name: String) {
     std::thread::spawn(move || {
-        if let Err(err) = sender.send(Event::Progress(scan_folder.clone())) {
-            error!("Failed to set initial progress: {err}");
-        }
-        let mut i = 0;
-        loop {
-            i += 1;
-            std::thread::sleep(Duration::from_secs(3));
-            if let Err(err) = sender.send(Event::Progress(format!(
-                "{scan_folder}{}",
-                (0..i).map(|_| '.').collect::<String>()
-            ))) {
-                error!("Failed to set initial progress: {err}");
+        let Some(path) = config.folder_path(&name) else {
+            return;
+        };
+        let root_path_bytes = config.name_prefix();
+        let mut largest = LargestFiles::default();
+        for entry in sequential_walk(&config, &path, None).filter_map(Result::ok) {
+            if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                continue;
             }
+            if let Ok(md) = entry.metadata() {
+                largest.push_with(md.len(), || LargeFile {
+                    size: md.len(),
+                    name: relative_name(root_path_bytes, entry.path()),
+                    modified: md.modified().ok(),
+                });
+            }
+        }
+        if let Err(err) = sender.send(Event::FolderLargestFiles(
+            generation,
+            name,
+            largest.to_sorted_vec(),
+        )) {
+            error!("Failed to emit largest files {err}");
+        }
+    });
+}
+
+/// Walk a folder on the current thread with the scan filters, staying on
+/// the scanned file systems.
+fn sequential_walk(config: &Config, path: &Path, max_depth: Option<usize>) -> ignore::Walk {
+    let root_devs = root_devices(config);
+    let file_filter = FileFilter::new(&config.filters);
+    walk_builder(&[path], config)
+        .max_depth(max_depth)
+        .filter_entry(move |entry| {
+            file_filter.check(entry)
+                || (entry.file_type().map(|e| e.is_dir()).unwrap_or(false)
+                    && entry
+                        .metadata()
+                        .is_ok_and(|md| on_root_device(&root_devs, entry.path(), &md)))
+        })
+        .build()
+}
+
+/// Device of each scanned folder when staying on one file system.
+fn root_devices(config: &Config) -> Vec<(&'static Path, u64)> {
+    if !config.one_file_system {
+        return Vec::new();
+    }
+    config
+        .root_paths
+        .iter()
+        .filter_map(|root| {
+            std::fs::metadata(root)
+                .inspect_err(|err| error!("Failed to read root {err}"))
+                .ok()
+                .map(|md| (root.as_path(), md.dev()))
+        })
+        .collect()
+}
+
+/// True if the symbolic link at `path` points inside a scanned folder.
+fn links_into_roots(root_paths: &[PathBuf], path: &Path) -> bool {
+    std::fs::canonicalize(path)
+        .is_ok_and(|target| root_paths.iter().any(|root| target.starts_with(root)))
+}
+
+/// True if the path is on the same device as the scanned folder it is in.
+fn on_root_device(root_devs: &[(&Path, u64)], path: &Path, md: &Metadata) -> bool {
+    root_devs
+        .iter()
+        .find(|(root, _)| path.starts_with(root))
+        .is_none_or(|(_, dev)| *dev == md.dev())
+}
+
+/// Spawn a thread that emits the scan progress counters until the scan
+/// finishes or is cancelled.
+fn start_progress_indicator(sender: &Sender<Event>, scan: &ScanHandle) {
+    let sender = sender.clone();
+    let scan = scan.clone();
+    let start = Instant::now();
+
+    std::thread::spawn(move || loop {
+        if scan.is_finished() || scan.is_cancelled() {
+            break;
+        }
+        if let Err(err) = sender.send(Event::Progress(scan.generation, scan.progress(start))) {
+            error!("Failed to emit progress: {err}");
+            break;
         }
+        std::thread::sleep(PROGRESS_INTERVAL);
     });
 }
 
-fn check_filename_filter(entry: &DirEntry, filters: &[Filter]) -> bool {
-    let filename_filter = || filters.iter().filter(|f| matches!(f, Filter::FileName(_)));
-    // Check filename filters.
-    if filename_filter().next().is_some() {
-        match entry.file_name().to_str() {
-            Some(filename) => filename_filter().any(|f| f.contains(filename)),
+/// Compiled file filters. Files must match one of the file name filters
+/// when there are any, and one of the extensions when there are any.
+#[derive(Debug, Default)]
+struct FileFilter {
+    has_file_name_filters: bool,
+    globs: GlobSet,
+    regexes: Vec<Regex>,
+    contains: Vec<String>,
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    older_than: Option<SystemTime>,
+    newer_than: Option<SystemTime>,
+}
+
+impl FileFilter {
+    /// Compile the filters. Patterns are validated when parsing arguments
+    /// so invalid ones are logged and skipped.
+    fn new(filters: &[Filter]) -> Self {
+        let mut globs = GlobSetBuilder::new();
+        let mut file_filter = Self::default();
+        for filter in filters {
+            match filter {
+                Filter::Extension(extension) => file_filter.extensions.push(extension.clone()),
+                Filter::Glob(glob) => match Glob::new(glob) {
+                    Ok(glob) => {
+                        globs.add(glob);
+                    }
+                    Err(err) => error!("Invalid glob filter {err}"),
+                },
+                Filter::Regex(regex) => match Regex::new(regex) {
+                    Ok(regex) => file_filter.regexes.push(regex),
+                    Err(err) => error!("Invalid regex filter {err}"),
+                },
+                Filter::Contains(text) => file_filter.contains.push(text.clone()),
+                Filter::MinSize(size) => {
+                    file_filter.min_size = file_filter.min_size.max(Some(*size));
+                }
+                Filter::MaxSize(size) => {
+                    file_filter.max_size =
+                        Some(file_filter.max_size.map_or(*size, |m| m.min(*size)));
+                }
+                Filter::OlderThan(time) => {
+                    let time = SystemTime::from(*time);
+                    file_filter.older_than =
+                        Some(file_filter.older_than.map_or(time, |t| t.min(time)));
+                }
+                Filter::NewerThan(time) => {
+                    file_filter.newer_than = file_filter.newer_than.max(Some((*time).into()));
+                }
+            }
+        }
+        file_filter.has_file_name_filters = filters.iter().any(Filter::is_file_name);
+        file_filter.globs = globs.build().unwrap_or_else(|err| {
+            error!("Invalid glob filters {err}");
+            GlobSet::empty()
+        });
+        file_filter
+    }
+
+    /// True if the file at `path` matches the filters.
+    fn matches(&self, path: &Path) -> bool {
+        self.matches_file_name(path) && self.matches_extension(path)
+    }
+
+    fn matches_file_name(&self, path: &Path) -> bool {
+        if !self.has_file_name_filters {
+            return true;
+        }
+        match path.file_name().and_then(|s| s.to_str()) {
+            Some(filename) => {
+                self.globs.is_match(filename)
+                    || self.regexes.iter().any(|regex| regex.is_match(filename))
+                    || self.contains.iter().any(|text| filename.contains(text))
+            }
             None => false,
         }
-    } else {
-        true
     }
-}
 
-fn check_file_extension_filter(entry: &DirEntry, filters: &[Filter]) -> bool {
-    let extension_filter = || filters.iter().filter(|f| matches!(f, Filter::Extension(_)));
-    // Check extensions filters.
-    if extension_filter().next().is_some() {
-        match entry.path().extension().and_then(|s| s.to_str()) {
-            Some(extension) => extension_filter().any(|f| f.contains(extension)),
+    fn matches_extension(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        match path.extension().and_then(|s| s.to_str()) {
+            Some(extension) => self.extensions.iter().any(|e| e == extension),
             None => false,
         }
-    } else {
-        true
+    }
+
+    /// True if there are filters needing the file metadata.
+    fn has_metadata_filters(&self) -> bool {
+        self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.older_than.is_some()
+            || self.newer_than.is_some()
+    }
+
+    /// True if a file of `size` bytes last modified at `modified` matches
+    /// the size and time filters.
+    fn matches_metadata(&self, size: u64, modified: SystemTime) -> bool {
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self.older_than.is_none_or(|time| modified < time)
+            && self.newer_than.is_none_or(|time| modified > time)
+    }
+
+    /// True if the entry is a file matching the filters.
+    fn check(&self, entry: &DirEntry) -> bool {
+        entry.file_type().map(|e| e.is_file()).unwrap_or(false)
+            && self.matches(entry.path())
+            && (!self.has_metadata_filters()
+                || entry.metadata().is_ok_and(|md| {
+                    md.modified()
+                        .is_ok_and(|modified| self.matches_metadata(md.len(), modified))
+                }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{collect_stats, relative_name, FileFilter, ScanProgress};
+    use crate::{
+        app::{Config, Filter, FolderStat},
+        args::Args,
+        event::Event,
+        tree::FolderKind,
+    };
+    use chrono::{Local, TimeDelta};
+    use clap::Parser;
+    use std::{
+        collections::HashMap,
+        fs,
+        os::unix::fs::symlink,
+        path::Path,
+        sync::mpsc,
+        time::{Duration, SystemTime},
+    };
+
+    /// Scan `root` following symbolic links, returning the stats of the
+    /// files directly inside each folder and the marked folders.
+    fn scan_following_links(
+        root: &Path,
+    ) -> (HashMap<String, FolderStat>, HashMap<String, FolderKind>) {
+        let args = Args::parse_from(["test", "-L", "-p", root.to_str().unwrap()]);
+        let (sender, receiver) = mpsc::channel();
+        let _scan = collect_stats(sender, Config::try_from(args).unwrap());
+        let mut folders = HashMap::new();
+        let mut marked = HashMap::new();
+        for event in receiver {
+            match event {
+                Event::FolderEvent(_, events) => {
+                    for (name, stats) in events {
+                        *folders.entry(name).or_default() += stats;
+                    }
+                }
+                Event::MarkFolder(_, name, kind) => {
+                    marked.insert(name, kind);
+                }
+                Event::ScanComplete(..) => break,
+                _ => (),
+            }
+        }
+        (folders, marked)
+    }
+
+    #[test]
+    fn test_follow_links_inside_roots() {
+        let root = std::env::temp_dir().join(format!("folder-stats-links-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("z/y/x/real")).unwrap();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("g")).unwrap();
+        fs::write(root.join("z/y/x/real/f"), [0; 1000]).unwrap();
+        fs::write(root.join("g/h"), [0; 10]).unwrap();
+        symlink(root.join("z/y/x/real"), root.join("a/link")).unwrap();
+        symlink("..", root.join("a/up")).unwrap();
+
+        // Links may be reached before the real folder, so scan a few times.
+        for _ in 0..5 {
+            let (folders, marked) = scan_following_links(&root);
+            assert_eq!(folders["/z/y/x/real"].size, 1000);
+            assert_eq!(folders["/g"].size, 10);
+            assert_eq!(marked.get("/a/link"), Some(&FolderKind::Symlink));
+            assert!(folders
+                .iter()
+                .all(|(name, stats)| !name.starts_with("/a/") || stats.files == 0));
+            assert_eq!(folders.values().map(|stats| stats.files).sum::<usize>(), 2);
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_relative_name() {
+        assert_eq!(relative_name(b"/data", Path::new("/data")), "");
+        assert_eq!(relative_name(b"/data", Path::new("/data/a/b")), "/a/b");
+        assert_eq!(relative_name(b"/", Path::new("/")), "");
+        assert_eq!(relative_name(b"/", Path::new("/proc")), "/proc");
+    }
+
+    #[test]
+    fn test_file_filter() {
+        let extension = FileFilter::new(&[Filter::Extension("rs".to_owned())]);
+        assert!(extension.matches(Path::new("/src/main.rs")));
+        assert!(!extension.matches(Path::new("/docs/index.rst")));
+
+        let names = FileFilter::new(&[
+            Filter::Glob("*.log.[0-9]".to_owned()),
+            Filter::Regex("^test_".to_owned()),
+        ]);
+        assert!(names.matches(Path::new("/var/app.log.1")));
+        assert!(names.matches(Path::new("/src/test_walker.rs")));
+        assert!(!names.matches(Path::new("/src/latest.txt")));
+
+        let contains = FileFilter::new(&[Filter::Contains("test".to_owned())]);
+        assert!(contains.matches(Path::new("/src/latest.txt")));
+
+        let now = Local::now();
+        let old = SystemTime::from(now - TimeDelta::days(400));
+        let metadata = FileFilter::new(&[
+            Filter::MinSize(100),
+            Filter::OlderThan(now - TimeDelta::days(365)),
+        ]);
+        assert!(metadata.matches_metadata(100, old));
+        assert!(!metadata.matches_metadata(99, old));
+        assert!(!metadata.matches_metadata(100, now.into()));
+    }
+
+    #[test]
+    fn test_progress_rate() {
+        let progress = ScanProgress {
+            files: 500,
+            bytes: 4096,
+            elapsed: Duration::from_millis(2000),
+            ..Default::default()
+        };
+        assert_eq!(progress.files_per_sec(), 250);
+        assert_eq!(progress.bytes_per_sec(), 2048);
+        assert_eq!(ScanProgress::default().files_per_sec(), 0);
     }
 }
@@ -0,0 +1,138 @@
+//! Watch the scanned folder for changes after a scan completes. Changed
+//! entries are emitted with their relative names so the folders holding
+//! them can be rescanned. Watches are added and changed folders rescanned
+//! on worker threads so large folders do not stall the Tui.
+use crate::{
+    app::{Config, FolderStat},
+    event::Event,
+    walker::{self, relative_name},
+};
+use anyhow::Result;
+use log::error;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::{HashMap, HashSet},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    sync::mpsc::{self, Sender},
+};
+
+/// Watches the scanned folder until dropped.
+#[derive(Debug)]
+pub struct FolderWatcher {
+    /// Dropping the sender stops the thread holding the watcher.
+    _stop: Sender<()>,
+}
+
+/// Changed folder to rescan.
+#[derive(Debug, Clone)]
+pub struct ChangedFolder {
+    /// Relative name of the folder.
+    pub name: String,
+    /// Sub folders already in the scanned hierarchy.
+    pub known_folders: HashSet<String>,
+}
+
+/// Rescanned stats of a changed folder.
+#[derive(Debug, Clone)]
+pub struct FolderUpdate {
+    /// Relative name of the folder.
+    pub name: String,
+    /// Stats of the files directly inside the folder.
+    pub files: FolderStat,
+    /// Sub folders found directly inside the folder.
+    pub folders: HashSet<String>,
+    /// Stats of the files directly inside each folder below new sub folders.
+    pub new_folders: HashMap<String, FolderStat>,
+}
+
+/// Watch the scanned folders recursively on a worker thread, emitting
+/// changes for a scan generation until the returned watcher is dropped.
+pub fn watch(sender: Sender<Event>, config: &Config, generation: u64) -> FolderWatcher {
+    let (stop, stopped) = mpsc::channel();
+    let root_paths = config.root_paths;
+    let root_path_bytes = config.name_prefix();
+    std::thread::spawn(move || {
+        match watch_roots(sender.clone(), root_paths, root_path_bytes, generation) {
+            // Hold the watcher until the receiver is disconnected.
+            Ok(_watcher) => while stopped.recv().is_ok() {},
+            Err(err) => {
+                if let Err(err) = sender.send(Event::WatchError(generation, err.to_string())) {
+                    error!("Failed to emit watch error {err}");
+                }
+            }
+        }
+    });
+    FolderWatcher { _stop: stop }
+}
+
+/// Add recursive watches for the scanned folders.
+fn watch_roots(
+    sender: Sender<Event>,
+    root_paths: &[PathBuf],
+    root_path_bytes: &'static [u8],
+    generation: u64,
+) -> Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let event = match result {
+            Ok(event) => event,
+            Err(err) => {
+                error!("Failed to watch {err}");
+                return;
+            }
+        };
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return;
+        }
+        for path in event
+            .paths
+            .iter()
+            .filter(|path| path.as_os_str().as_bytes().starts_with(root_path_bytes))
+        {
+            let name = relative_name(root_path_bytes, path);
+            if let Err(err) = sender.send(Event::FolderChanged(generation, name)) {
+                error!("Failed to emit folder change {err}");
+            }
+        }
+    })?;
+    for root in root_paths {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+    Ok(watcher)
+}
+
+/// Rescan changed folders on a worker thread, emitting their updated
+/// stats for a scan generation. New sub folders are scanned entirely.
+pub fn rescan_folders(
+    sender: Sender<Event>,
+    config: Config,
+    generation: u64,
+    changed: Vec<ChangedFolder>,
+) {
+    std::thread::spawn(move || {
+        let updates = changed
+            .into_iter()
+            .map(|folder| {
+                let mut scanned = walker::scan_folder(&config, &folder.name, Some(1));
+                let files = scanned.remove(&folder.name).unwrap_or_default();
+                let new_folders = scanned
+                    .keys()
+                    .filter(|child| !folder.known_folders.contains(*child))
+                    .flat_map(|child| walker::scan_folder(&config, child, None))
+                    .collect();
+                FolderUpdate {
+                    name: folder.name,
+                    files,
+                    folders: scanned.into_keys().collect(),
+                    new_folders,
+                }
+            })
+            .collect();
+        if let Err(err) = sender.send(Event::FoldersRescanned(generation, updates)) {
+            error!("Failed to emit rescanned folders {err}");
+        }
+    });
+}